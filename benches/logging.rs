@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ftail::Ftail;
+use log::LevelFilter;
+
+// The `log` crate only allows one logger, so all benchmarks share a single file logger.
+
+fn logging(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("ftail-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    Ftail::new()
+        .single_file(dir.join("bench.log").to_str().unwrap(), false, LevelFilter::Info)
+        .init()
+        .unwrap();
+
+    c.bench_function("single_file/message", |b| {
+        b.iter(|| log::info!("This is an info message"))
+    });
+
+    c.bench_function("single_file/message_with_args", |b| {
+        b.iter(|| log::info!(target: "bench", "user {} logged in from {}", 42, "127.0.0.1"))
+    });
+
+    c.bench_function("single_file/below_max_level", |b| {
+        b.iter(|| log::debug!("user {} logged in from {}", 42, "127.0.0.1"))
+    });
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, logging);
+criterion_main!(benches);
@@ -31,11 +31,7 @@ struct CustomLogger {
 
 impl Log for CustomLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
-        metadata.level() <= self.config.level_filter
+        self.config.is_enabled(metadata.level())
     }
 
     fn log(&self, record: &log::Record) {
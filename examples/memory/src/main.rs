@@ -0,0 +1,30 @@
+use ftail::{
+    drivers::memory::{Capacity, MemoryBuffer},
+    Ftail,
+};
+use log::LevelFilter;
+
+// This example demonstrates how to keep the last log messages in memory.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let buffer = MemoryBuffer::new(Capacity::Records(3));
+
+    Ftail::new().memory(&buffer, LevelFilter::Trace).init()?;
+
+    log::trace!("This is a trace message");
+
+    log::debug!("This is a debug message");
+
+    log::info!(target: "foo", "bar");
+
+    log::warn!("This is a warning message");
+
+    log::error!("This is an error message");
+
+    // only the last 3 messages are kept
+    for record in buffer.drain() {
+        println!("{}", record);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use ftail::{
+    drivers::syslog::{Facility, Syslog},
+    Ftail,
+};
+use log::LevelFilter;
+
+// This example demonstrates how to send log messages to a syslog daemon listening on UDP port 514.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    Ftail::new()
+        .syslog(
+            Syslog::udp("127.0.0.1:514")
+                .facility(Facility::Local0)
+                .app_name("example"),
+            LevelFilter::Trace,
+        )
+        .init()?;
+
+    log::trace!("This is a trace message");
+
+    log::debug!("This is a debug message");
+
+    log::info!(target: "foo", "bar");
+
+    log::warn!("This is a warning message");
+
+    log::error!("This is an error message");
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    thread::Thread,
+};
+
+static HOSTNAME: OnceLock<String> = OnceLock::new();
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The hostname of the machine, looked up once.
+pub fn hostname() -> &'static str {
+    HOSTNAME.get_or_init(|| gethostname::gethostname().to_string_lossy().into_owned())
+}
+
+/// The id of the current process.
+pub fn pid() -> u32 {
+    std::process::id()
+}
+
+/// The id of the current thread, numbered from 1 in the order the threads first log.
+pub fn thread_id() -> u64 {
+    THREAD_ID.with(|id| *id)
+}
+
+/// The current thread, whose name is shown with `show_thread_name`.
+pub fn thread() -> Thread {
+    std::thread::current()
+}
@@ -0,0 +1,38 @@
+use log::Log;
+
+use crate::{record::OwnedRecord, Config};
+
+/// A logger that passes an owned copy of each record to a closure, e.g. to send it over a
+/// channel to a TUI or a test harness.
+pub struct CallbackLogger {
+    callback: Box<dyn Fn(OwnedRecord) + Send + Sync>,
+    config: Config,
+}
+
+impl CallbackLogger {
+    pub fn new<F>(callback: F, config: Config) -> Self
+    where
+        F: Fn(OwnedRecord) + Send + Sync + 'static,
+    {
+        CallbackLogger {
+            callback: Box::new(callback),
+            config,
+        }
+    }
+}
+
+impl Log for CallbackLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        (self.callback)(OwnedRecord::new(record));
+    }
+
+    fn flush(&self) {}
+}
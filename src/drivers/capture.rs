@@ -0,0 +1,132 @@
+use log::{Level, Log};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    formatters::{default::DefaultFormatter, Formatter},
+    Config,
+};
+
+/// A log record captured by the [`CaptureLogger`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub formatted: String,
+    pub module_path: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+/// A handle to the records captured by the `capture` driver.
+#[derive(Clone, Default)]
+pub struct Captured {
+    records: Arc<Mutex<Vec<CapturedRecord>>>,
+}
+
+impl Captured {
+    /// Create a new, empty handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a copy of the captured records, oldest first.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Remove all captured records.
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// Whether a record with the given level and target was captured whose message matches the
+    /// regular expression.
+    pub fn contains(&self, level: Level, target: &str, pattern: &str) -> bool {
+        let regex = regex::Regex::new(pattern).expect("invalid regular expression");
+
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|r| r.level == level && r.target == target && regex.is_match(&r.message))
+    }
+
+    /// Panic unless a record with the given level and target was captured whose message matches
+    /// the regular expression.
+    #[track_caller]
+    pub fn assert_logged(&self, level: Level, target: &str, pattern: &str) {
+        if !self.contains(level, target, pattern) {
+            panic!(
+                "expected a {} record with target `{}` matching `{}`, captured:\n{}",
+                level,
+                target,
+                pattern,
+                self.describe()
+            );
+        }
+    }
+
+    /// Panic if a record with the given level and target was captured whose message matches the
+    /// regular expression.
+    #[track_caller]
+    pub fn assert_not_logged(&self, level: Level, target: &str, pattern: &str) {
+        if self.contains(level, target, pattern) {
+            panic!(
+                "expected no {} record with target `{}` matching `{}`, captured:\n{}",
+                level,
+                target,
+                pattern,
+                self.describe()
+            );
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| format!("  {} {} {}", r.level, r.target, r.message))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// A logger that captures log records so tests can assert on them.
+pub struct CaptureLogger {
+    captured: Captured,
+    config: Config,
+}
+
+impl CaptureLogger {
+    pub fn new(captured: Captured, config: Config) -> Self {
+        CaptureLogger { captured, config }
+    }
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = DefaultFormatter::new(record, &self.config);
+
+        self.captured.records.lock().unwrap().push(CapturedRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            formatted: formatter.format(),
+            module_path: record.module_path().map(|m| m.to_string()),
+            file: record.file().map(|f| f.to_string()),
+            line: record.line(),
+        });
+    }
+
+    fn flush(&self) {}
+}
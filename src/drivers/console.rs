@@ -1,9 +1,6 @@
-use log::{LevelFilter, Log};
+use log::Log;
 
-use crate::{
-    formatters::{default::DefaultFormatter, Formatter},
-    Config,
-};
+use crate::{formatters::default::DefaultFormatter, helpers::format_line, Config};
 
 /// A logger that logs messages to the console.
 pub struct ConsoleLogger {
@@ -18,11 +15,7 @@ impl ConsoleLogger {
 
 impl Log for ConsoleLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
-        metadata.level() <= self.config.level_filter
+        self.config.is_enabled(metadata.level())
     }
 
     fn log(&self, record: &log::Record) {
@@ -32,7 +25,7 @@ impl Log for ConsoleLogger {
 
         let formatter = DefaultFormatter::new(record, &self.config);
 
-        println!("{}", formatter.format());
+        format_line(&formatter, |line| print!("{}", line));
     }
 
     fn flush(&self) {}
@@ -1,4 +1,4 @@
-use log::{LevelFilter, Log};
+use log::Log;
 use std::{
     fs::File,
     io::{LineWriter, Write},
@@ -8,15 +8,17 @@ use std::{
 
 use crate::{
     error::FtailError,
-    formatters::{default::DefaultFormatter, Formatter},
-    helpers::rotate_if_exceeds_max_file_size,
+    helpers::{
+        format_file_line, open_append, reopen_if_moved, rotate_if_exceeds_max_file_size, FileLock,
+    },
+    timestamp::Timestamp,
+    writer::write_datetime,
     Config,
 };
 
 /// A logger that logs messages to a daily log file.
 pub struct DailyFileLogger {
     file: Mutex<LineWriter<File>>,
-    file_path: PathBuf,
     dir: String,
     current_date: Mutex<String>,
     config: Config,
@@ -24,7 +26,7 @@ pub struct DailyFileLogger {
 
 impl DailyFileLogger {
     pub fn new(dir: &str, config: Config) -> Result<Self, FtailError> {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let today = date(&Timestamp::now(), &config);
         let path = format!("{}/{}.log", dir, today);
 
         let file = std::fs::OpenOptions::new()
@@ -41,41 +43,52 @@ impl DailyFileLogger {
 
         Ok(DailyFileLogger {
             file: Mutex::new(LineWriter::new(file)),
-            file_path: PathBuf::from(path),
             dir: dir.to_string(),
             current_date: Mutex::new(today),
             config,
         })
     }
 
-    fn rotate_daily_file(&self) {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    fn file_path(&self, date: &str) -> PathBuf {
+        PathBuf::from(format!("{}/{}.log", self.dir, date))
+    }
+
+    fn rotate_daily_file(&self, file: &mut LineWriter<File>) -> PathBuf {
+        let today = date(&Timestamp::current(), &self.config);
         let mut current_date = self.current_date.lock().unwrap();
 
         if *current_date != today {
-            let path = format!("{}/{}.log", self.dir, today);
-
-            let new_file = std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(path)
-                .unwrap();
-
-            let mut file = self.file.lock().unwrap();
+            let new_file = open_append(&self.file_path(&today)).unwrap();
 
             *file = LineWriter::new(new_file);
             *current_date = today;
         }
+
+        self.file_path(&current_date)
     }
 }
 
+/// The date of the log file, in the timezone of the logged datetimes when it's set explicitly,
+/// otherwise in the local timezone.
+fn date(timestamp: &Timestamp, config: &Config) -> String {
+    #[cfg(feature = "timezone")]
+    let explicit_timezone = config.explicit_timezone;
+    #[cfg(not(feature = "timezone"))]
+    let explicit_timezone = false;
+
+    if config.utc_offset.is_none() && !explicit_timezone {
+        return timestamp.datetime.format("%Y-%m-%d").to_string();
+    }
+
+    let mut date = String::new();
+    write_datetime(timestamp.datetime, config, None, "%Y-%m-%d", &mut date).unwrap();
+
+    date
+}
+
 impl Log for DailyFileLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
-        metadata.level() <= self.config.level_filter
+        self.config.is_enabled(metadata.level())
     }
 
     fn log(&self, record: &log::Record) {
@@ -83,13 +96,25 @@ impl Log for DailyFileLogger {
             return;
         }
 
-        rotate_if_exceeds_max_file_size(&self.file, self.file_path.clone(), &self.config);
-        self.rotate_daily_file();
+        let mut file = self.file.lock().unwrap();
 
-        let formatter = DefaultFormatter::new(record, &self.config);
+        let _lock = if self.config.multi_process {
+            Some(FileLock::acquire(&PathBuf::from(format!("{}/.ftail.lock", self.dir))).unwrap())
+        } else {
+            None
+        };
 
-        let mut file = self.file.lock().unwrap();
-        writeln!(file, "{}", formatter.format()).unwrap();
+        let file_path = self.rotate_daily_file(&mut file);
+
+        if self.config.multi_process {
+            reopen_if_moved(&mut file, &file_path);
+        }
+
+        rotate_if_exceeds_max_file_size(&mut file, &file_path, &self.config);
+
+        format_file_line(record, &self.config, |line| {
+            file.write_all(line.as_bytes()).unwrap()
+        });
         file.flush().unwrap();
     }
 
@@ -0,0 +1,128 @@
+use log::{Level, Log};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{timestamp::Timestamp, Config};
+
+struct BufferedRecord {
+    timestamp: Timestamp,
+    level: Level,
+    target: String,
+    message: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+/// Identifies the buffers of each logger among the buffers of a thread.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The buffered records of the current thread per logger, dropped when the thread exits.
+    static BUFFERS: RefCell<HashMap<usize, VecDeque<BufferedRecord>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// A logger that keeps the last debug and trace messages of each thread in memory and only
+/// writes them to the wrapped driver when an error is logged on the same thread.
+pub struct ErrorContextLogger {
+    driver: Box<dyn Log + Send + Sync>,
+    capacity: usize,
+    id: usize,
+    config: Config,
+}
+
+impl ErrorContextLogger {
+    /// The wrapped driver should accept all levels, the level filter of `config` decides which
+    /// messages are written directly.
+    pub fn new(driver: Box<dyn Log + Send + Sync>, capacity: usize, config: Config) -> Self {
+        ErrorContextLogger {
+            driver,
+            capacity,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            config,
+        }
+    }
+
+    fn is_passed_through(&self, level: Level) -> bool {
+        self.config.is_enabled(level)
+    }
+
+    fn buffer(&self, record: &log::Record) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let buffered = BufferedRecord {
+            timestamp: Timestamp::current(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            module_path: record.module_path().map(|m| m.to_string()),
+            file: record.file().map(|f| f.to_string()),
+            line: record.line(),
+        };
+
+        BUFFERS.with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            let buffer = buffers.entry(self.id).or_default();
+
+            if buffer.len() >= self.capacity {
+                buffer.pop_front();
+            }
+
+            buffer.push_back(buffered);
+        });
+    }
+
+    fn flush_context(&self) {
+        let buffer = BUFFERS.with(|buffers| buffers.borrow_mut().remove(&self.id));
+
+        // replay each record with the time it was logged, not the time of the error
+        for record in buffer.into_iter().flatten() {
+            record.timestamp.scope(|| {
+                self.driver.log(
+                    &log::Record::builder()
+                        .level(record.level)
+                        .target(&record.target)
+                        .args(format_args!("{}", record.message))
+                        .module_path(record.module_path.as_deref())
+                        .file(record.file.as_deref())
+                        .line(record.line)
+                        .build(),
+                )
+            });
+        }
+    }
+}
+
+impl Log for ErrorContextLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.is_passed_through(metadata.level()) || metadata.level() >= Level::Debug
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if !self.is_passed_through(record.level()) {
+            self.buffer(record);
+
+            return;
+        }
+
+        if record.level() == Level::Error {
+            self.flush_context();
+        }
+
+        self.driver.log(record);
+    }
+
+    fn flush(&self) {
+        self.driver.flush();
+    }
+}
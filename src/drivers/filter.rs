@@ -0,0 +1,33 @@
+use log::Log;
+
+use crate::filter::Filter;
+
+/// A logger that only passes the messages matching the filter to the wrapped driver.
+pub struct FilterLogger {
+    driver: Box<dyn Log + Send + Sync>,
+    filter: Filter,
+}
+
+impl FilterLogger {
+    pub(crate) fn new(driver: Box<dyn Log + Send + Sync>, filter: Filter) -> Self {
+        FilterLogger { driver, filter }
+    }
+}
+
+impl Log for FilterLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.filter.matches(metadata) && self.driver.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) || !self.filter.matches_record(record) {
+            return;
+        }
+
+        self.driver.log(record);
+    }
+
+    fn flush(&self) {
+        self.driver.flush();
+    }
+}
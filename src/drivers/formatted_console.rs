@@ -1,9 +1,6 @@
-use log::{LevelFilter, Log};
+use log::Log;
 
-use crate::{
-    formatters::{readable::ReadableFormatter, Formatter},
-    Config,
-};
+use crate::{formatters::readable::ReadableFormatter, helpers::format_line, Config};
 
 /// A logger that logs formatted messages to the console.
 pub struct FormattedConsoleLogger {
@@ -18,11 +15,7 @@ impl FormattedConsoleLogger {
 
 impl Log for FormattedConsoleLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
-        metadata.level() <= self.config.level_filter
+        self.config.is_enabled(metadata.level())
     }
 
     fn log(&self, record: &log::Record) {
@@ -32,7 +25,7 @@ impl Log for FormattedConsoleLogger {
 
         let formatter = ReadableFormatter::new(record, &self.config);
 
-        println!("{}", formatter.format());
+        format_line(&formatter, |line| print!("{}", line));
     }
 
     fn flush(&self) {}
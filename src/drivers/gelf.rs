@@ -0,0 +1,167 @@
+use log::Log;
+use std::{
+    net::UdpSocket,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    error::FtailError,
+    formatters::{gelf::GelfFormatter, Formatter},
+    helpers::connect_udp,
+    Config,
+};
+
+/// The header of a chunk: the magic bytes, the message id, the sequence number and count.
+const CHUNK_HEADER_LEN: usize = 12;
+
+/// GELF doesn't allow more chunks per message.
+const MAX_CHUNKS: usize = 128;
+
+/// The configuration of the `gelf` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gelf {
+    address: String,
+    host: Option<String>,
+    chunk_size: usize,
+    #[cfg(feature = "gzip")]
+    compress: bool,
+}
+
+impl Gelf {
+    /// Send the messages to a GELF UDP input, e.g. `127.0.0.1:12201`.
+    pub fn udp(address: &str) -> Self {
+        Gelf {
+            address: address.to_string(),
+            host: None,
+            chunk_size: 1420,
+            #[cfg(feature = "gzip")]
+            compress: false,
+        }
+    }
+
+    /// Set the `host` field, the hostname of the machine by default.
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_string());
+
+        self
+    }
+
+    /// Set the maximum size of a datagram, 1420 bytes by default. Larger messages are split into
+    /// chunks, and messages that need more than 128 chunks are dropped.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(CHUNK_HEADER_LEN + 1);
+
+        self
+    }
+
+    /// Compress the messages with gzip.
+    #[cfg(feature = "gzip")]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+
+        self
+    }
+}
+
+/// A logger that sends GELF messages to a UDP input, e.g. of Graylog.
+pub struct GelfLogger {
+    socket: UdpSocket,
+    gelf: Gelf,
+    host: String,
+    message_id: AtomicU64,
+    config: Config,
+}
+
+impl GelfLogger {
+    pub fn new(gelf: Gelf, config: Config) -> Result<Self, FtailError> {
+        let socket = connect_udp(&gelf.address).map_err(FtailError::IoError)?;
+
+        let host = gelf
+            .host
+            .clone()
+            .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().into_owned());
+
+        // message ids only need to be unique per sender for a few seconds
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+
+        Ok(GelfLogger {
+            socket,
+            gelf,
+            host,
+            message_id: AtomicU64::new(seed),
+            config,
+        })
+    }
+
+    #[cfg(feature = "gzip")]
+    fn encode(&self, message: String) -> Vec<u8> {
+        use std::io::Write;
+
+        if !self.gelf.compress {
+            return message.into_bytes();
+        }
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(message.as_bytes()).unwrap();
+
+        encoder.finish().unwrap()
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn encode(&self, message: String) -> Vec<u8> {
+        message.into_bytes()
+    }
+
+    /// Send the message in one datagram, or in chunks if it's larger than the chunk size.
+    fn send(&self, payload: &[u8]) {
+        if payload.len() <= self.gelf.chunk_size {
+            let _ = self.socket.send(payload);
+
+            return;
+        }
+
+        let chunks = payload.chunks(self.gelf.chunk_size - CHUNK_HEADER_LEN);
+
+        if chunks.len() > MAX_CHUNKS {
+            return;
+        }
+
+        let message_id = self
+            .message_id
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes();
+        let count = chunks.len() as u8;
+
+        let mut datagram = Vec::with_capacity(self.gelf.chunk_size);
+
+        for (sequence, chunk) in chunks.enumerate() {
+            datagram.clear();
+            datagram.extend_from_slice(&[0x1e, 0x0f]);
+            datagram.extend_from_slice(&message_id);
+            datagram.extend_from_slice(&[sequence as u8, count]);
+            datagram.extend_from_slice(chunk);
+
+            let _ = self.socket.send(&datagram);
+        }
+    }
+}
+
+impl Log for GelfLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = GelfFormatter::new(record, &self.host).format();
+
+        self.send(&self.encode(message));
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,707 @@
+use log::{Level, Log};
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Condvar, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    drivers::otlp::Otlp, error::FtailError, formatters::otlp::write_request, json,
+    record::OwnedRecord, timestamp::TimestampFormat, writer::write_datetime, Config,
+};
+
+/// How long connecting, sending a batch and reading the response may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long `flush` waits for the buffered records to be sent.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The body format of the requests of the `http` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpBody {
+    /// One JSON object per line, with the `timestamp`, `level`, `target`, `message`, `file`,
+    /// `line` and key-values of the record. The timestamp is in the `timestamp_format`, RFC 3339
+    /// by default.
+    Ndjson,
+    /// The JSON body of the Loki push API, with the level and target as labels. The timestamps
+    /// are nanoseconds since the Unix epoch, as the API requires.
+    Loki,
+    /// The body of the Elasticsearch bulk API, which creates a document per record in the index.
+    /// The `@timestamp` is always RFC 3339, so it's mapped as a date.
+    ElasticsearchBulk(String),
+    /// An OTLP/JSON logs export request with the resource, e.g. for the `/v1/logs` endpoint of
+    /// an OpenTelemetry Collector.
+    Otlp(Otlp),
+}
+
+impl HttpBody {
+    fn content_type(&self) -> &'static str {
+        match self {
+            HttpBody::Ndjson | HttpBody::ElasticsearchBulk(_) => "application/x-ndjson",
+            HttpBody::Loki | HttpBody::Otlp(_) => "application/json",
+        }
+    }
+}
+
+/// The configuration of the `http` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Http {
+    url: String,
+    body: HttpBody,
+    headers: Vec<(String, String)>,
+    batch_size: usize,
+    batch_bytes: usize,
+    batch_interval: Duration,
+    capacity: usize,
+    retries: u32,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    dead_letter_path: Option<String>,
+}
+
+impl Http {
+    /// POST the records to an `http://` URL, e.g. `http://127.0.0.1:3100/loki/api/v1/push`.
+    pub fn new(url: &str) -> Self {
+        Http {
+            url: url.to_string(),
+            body: HttpBody::Ndjson,
+            headers: Vec::new(),
+            batch_size: 100,
+            batch_bytes: 1024 * 1024,
+            batch_interval: Duration::from_secs(1),
+            capacity: 10_000,
+            retries: 3,
+            min_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            dead_letter_path: None,
+        }
+    }
+
+    /// Set the body format, NDJSON by default.
+    pub fn body(mut self, body: HttpBody) -> Self {
+        self.body = body;
+
+        self
+    }
+
+    /// Add a header to the requests, e.g. `Authorization`. Line breaks are removed from the name
+    /// and value, so they can't add other headers.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .push((strip_line_breaks(name), strip_line_breaks(value)));
+
+        self
+    }
+
+    /// Send a batch once it has this many records, 100 by default.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+
+        self
+    }
+
+    /// Send a batch once its records have about this many bytes, 1 MB by default.
+    pub fn batch_bytes(mut self, batch_bytes: usize) -> Self {
+        self.batch_bytes = batch_bytes;
+
+        self
+    }
+
+    /// Send a batch once its oldest record has waited this long, 1 second by default.
+    pub fn batch_interval(mut self, batch_interval: Duration) -> Self {
+        self.batch_interval = batch_interval;
+
+        self
+    }
+
+    /// Set the number of records kept in memory while the endpoint is unavailable, 10 000 by
+    /// default. The oldest records are dropped when the buffer is full, and written to the
+    /// dead-letter file or otherwise counted in a warning sent with the next batch.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+
+        self
+    }
+
+    /// Retry a failed batch this many times, 3 by default. Batches are retried when the
+    /// endpoint can't be reached or responds with status 429 or 5xx.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+
+        self
+    }
+
+    /// Set the delay before the first retry, which doubles after every failed retry up to `max`.
+    /// 500 milliseconds and 30 seconds by default.
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max;
+
+        self
+    }
+
+    /// Append the bodies of the batches that couldn't be delivered, and of the records dropped
+    /// when the buffer is full, to a file.
+    pub fn dead_letter(mut self, path: &str) -> Self {
+        self.dead_letter_path = Some(path.to_string());
+
+        self
+    }
+}
+
+fn strip_line_breaks(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '\r' | '\n'))
+        .collect()
+}
+
+/// The parts of an `http://` URL.
+struct Url {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Option<Url> {
+        let rest = url.strip_prefix("http://")?;
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+
+        // IPv6 literals are bracketed, e.g. `[::1]:4318`
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(rest) => {
+                let (host, port) = rest.split_once(']')?;
+
+                match port {
+                    "" => (host, None),
+                    port => (host, Some(port.strip_prefix(':')?)),
+                }
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+
+        let port = match port {
+            Some(port) => port.parse().ok()?,
+            None => 80,
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Url {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// The value of the `Host` header.
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Connect to the first resolved address that accepts the connection.
+    fn connect(&self) -> std::io::Result<TcpStream> {
+        let mut last_error = None;
+
+        for address in (self.host.as_str(), self.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, REQUEST_TIMEOUT) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| std::io::ErrorKind::NotFound.into()))
+    }
+}
+
+/// The approximate size of the record in a body.
+fn approximate_len(record: &OwnedRecord) -> usize {
+    let key_values: usize = record
+        .key_values
+        .iter()
+        .map(|(key, _)| key.len() + 24)
+        .sum();
+
+    record.message.len()
+        + record.target.len()
+        + record.file.as_ref().map_or(0, String::len)
+        + key_values
+        + 96
+}
+
+/// Write the body of a batch.
+fn write_body(body: &HttpBody, entries: &[OwnedRecord], config: &Config) -> String {
+    let mut result = String::new();
+
+    match body {
+        HttpBody::Ndjson => {
+            for entry in entries {
+                let timestamp_format = config.timestamp_format.unwrap_or(TimestampFormat::Rfc3339);
+
+                write_document(&mut result, entry, "timestamp", timestamp_format, config);
+                result.push('\n');
+            }
+        }
+        HttpBody::ElasticsearchBulk(index) => {
+            for entry in entries {
+                result.push_str("{\"create\":{\"_index\":");
+                json::write_string(&mut result, index).unwrap();
+                result.push_str("}}\n");
+                write_document(
+                    &mut result,
+                    entry,
+                    "@timestamp",
+                    TimestampFormat::Rfc3339,
+                    config,
+                );
+                result.push('\n');
+            }
+        }
+        HttpBody::Loki => write_loki(&mut result, entries),
+        HttpBody::Otlp(otlp) => write_request(&mut result, otlp, entries).unwrap(),
+    }
+
+    result
+}
+
+fn write_document(
+    w: &mut String,
+    entry: &OwnedRecord,
+    timestamp_key: &str,
+    timestamp_format: TimestampFormat,
+    config: &Config,
+) {
+    // the Unix epoch timestamps are JSON numbers
+    let quote = match timestamp_format {
+        TimestampFormat::Rfc3339 | TimestampFormat::Rfc3339Utc => "\"",
+        _ => "",
+    };
+
+    w.push('{');
+    json::write_string(w, timestamp_key).unwrap();
+    w.push(':');
+    w.push_str(quote);
+    write_datetime(entry.datetime, config, Some(timestamp_format), "", w).unwrap();
+    w.push_str(quote);
+    write!(w, ",\"level\":\"{}\",\"target\":", entry.level).unwrap();
+    json::write_string(w, &entry.target).unwrap();
+    w.push_str(",\"message\":");
+    json::write_string(w, &entry.message).unwrap();
+
+    if let Some(file) = &entry.file {
+        w.push_str(",\"file\":");
+        json::write_string(w, file).unwrap();
+    }
+
+    if let Some(line) = entry.line {
+        write!(w, ",\"line\":{}", line).unwrap();
+    }
+
+    for (key, value) in &entry.key_values {
+        w.push(',');
+        json::write_string(w, key).unwrap();
+        w.push(':');
+        value.write_json(w).unwrap();
+    }
+
+    w.push('}');
+}
+
+/// Write the Loki push body, with a stream per level and target.
+fn write_loki(w: &mut String, entries: &[OwnedRecord]) {
+    let mut streams: Vec<(Level, &str, Vec<&OwnedRecord>)> = Vec::new();
+
+    for entry in entries {
+        match streams
+            .iter_mut()
+            .find(|(level, target, _)| *level == entry.level && *target == entry.target)
+        {
+            Some((_, _, stream)) => stream.push(entry),
+            None => streams.push((entry.level, &entry.target, vec![entry])),
+        }
+    }
+
+    w.push_str("{\"streams\":[");
+
+    for (i, (level, target, entries)) in streams.iter().enumerate() {
+        if i > 0 {
+            w.push(',');
+        }
+
+        write!(
+            w,
+            "{{\"stream\":{{\"level\":\"{}\",\"target\":",
+            level.as_str().to_lowercase()
+        )
+        .unwrap();
+        json::write_string(w, target).unwrap();
+        w.push_str("},\"values\":[");
+
+        for (j, entry) in entries.iter().enumerate() {
+            if j > 0 {
+                w.push(',');
+            }
+
+            let nanos = entry.datetime.timestamp() as i128 * 1_000_000_000
+                + entry.datetime.timestamp_subsec_nanos() as i128;
+
+            write!(w, "[\"{}\",", nanos).unwrap();
+            json::write_string(w, &entry.message).unwrap();
+            w.push(']');
+        }
+
+        w.push_str("]}");
+    }
+
+    w.push_str("]}");
+}
+
+/// A record that reports the records dropped from a full buffer.
+fn dropped_warning(dropped: usize) -> OwnedRecord {
+    OwnedRecord {
+        datetime: chrono::Local::now(),
+        level: Level::Warn,
+        target: "ftail".to_string(),
+        message: match dropped {
+            1 => "1 record was dropped because the buffer was full".to_string(),
+            dropped => format!(
+                "{} records were dropped because the buffer was full",
+                dropped
+            ),
+        },
+        module_path: None,
+        file: None,
+        line: None,
+        key_values: Vec::new(),
+    }
+}
+
+/// The outcome of a request.
+#[derive(PartialEq)]
+enum Delivery {
+    Delivered,
+    /// The endpoint couldn't be reached or responded with status 429 or 5xx.
+    Retry,
+    /// The endpoint rejected the batch.
+    Rejected,
+}
+
+fn post(url: &Url, http: &Http, body: &str) -> Delivery {
+    match try_post(url, http, body) {
+        Ok(status) if (200..300).contains(&status) => Delivery::Delivered,
+        Ok(status) if status == 429 || status >= 500 => Delivery::Retry,
+        Ok(_) => Delivery::Rejected,
+        Err(_) => Delivery::Retry,
+    }
+}
+
+/// Send the request and return the status code of the response.
+fn try_post(url: &Url, http: &Http, body: &str) -> std::io::Result<u16> {
+    let mut stream = url.connect()?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        url.path,
+        url.authority(),
+        http.body.content_type(),
+        body.len()
+    );
+
+    for (name, value) in &http.headers {
+        write!(request, "{}: {}\r\n", name, value).unwrap();
+    }
+
+    request.push_str("\r\n");
+
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+
+    // e.g. `HTTP/1.1 200 OK`
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| std::io::ErrorKind::InvalidData.into())
+}
+
+struct State {
+    /// The records with the time they were queued.
+    queue: VecDeque<(Instant, OwnedRecord)>,
+    bytes: usize,
+    /// The number of records dropped from a full buffer without a dead-letter file.
+    dropped: usize,
+    flush: bool,
+    sending: bool,
+    shutdown: bool,
+}
+
+impl State {
+    /// When the oldest record in the queue was queued.
+    fn oldest(&self) -> Option<Instant> {
+        self.queue.front().map(|(queued, _)| *queued)
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+    dead_letter: Mutex<Option<std::fs::File>>,
+}
+
+impl Shared {
+    fn write_dead_letter(&self, body: &str) {
+        if let Some(dead_letter) = self.dead_letter.lock().unwrap().as_mut() {
+            let _ = dead_letter.write_all(body.as_bytes());
+
+            if !body.ends_with('\n') {
+                let _ = dead_letter.write_all(b"\n");
+            }
+        }
+    }
+}
+
+/// A logger that sends batches of records to an HTTP endpoint from a background thread.
+pub struct HttpLogger {
+    shared: Arc<Shared>,
+    capacity: usize,
+    body: HttpBody,
+    has_dead_letter: bool,
+    worker: Option<JoinHandle<()>>,
+    config: Config,
+}
+
+impl HttpLogger {
+    pub fn new(http: Http, config: Config) -> Result<Self, FtailError> {
+        let url = Url::parse(&http.url).ok_or_else(|| FtailError::UrlError(http.url.clone()))?;
+
+        let dead_letter = match &http.dead_letter_path {
+            Some(path) => Some(
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(FtailError::IoError)?,
+            ),
+            None => None,
+        };
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                bytes: 0,
+                dropped: 0,
+                flush: false,
+                sending: false,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+            dead_letter: Mutex::new(dead_letter),
+        });
+
+        let capacity = http.capacity;
+        let body = http.body.clone();
+        let has_dead_letter = http.dead_letter_path.is_some();
+
+        let worker = {
+            let shared = shared.clone();
+            let config = config.clone();
+
+            std::thread::Builder::new()
+                .name("ftail-http".to_string())
+                .spawn(move || run(&shared, &http, &url, &config))
+                .map_err(FtailError::IoError)?
+        };
+
+        Ok(HttpLogger {
+            shared,
+            capacity,
+            body,
+            has_dead_letter,
+            worker: Some(worker),
+            config,
+        })
+    }
+}
+
+/// Send a batch once it's full or the oldest record has waited for the batch interval.
+fn run(shared: &Shared, http: &Http, url: &Url, config: &Config) {
+    loop {
+        let entries = {
+            let mut state = shared.state.lock().unwrap();
+
+            loop {
+                let is_full =
+                    state.queue.len() >= http.batch_size || state.bytes >= http.batch_bytes;
+                let is_due = state
+                    .oldest()
+                    .is_some_and(|oldest| oldest.elapsed() >= http.batch_interval);
+
+                if !state.queue.is_empty() && (is_full || is_due || state.flush || state.shutdown) {
+                    break;
+                }
+
+                if state.shutdown {
+                    return;
+                }
+
+                state.flush = false;
+
+                state = match state.oldest() {
+                    Some(oldest) => {
+                        let timeout = http.batch_interval.saturating_sub(oldest.elapsed());
+                        shared.changed.wait_timeout(state, timeout).unwrap().0
+                    }
+                    None => shared.changed.wait(state).unwrap(),
+                };
+            }
+
+            let mut entries = Vec::new();
+            let mut bytes = 0;
+
+            while entries.len() < http.batch_size
+                && (entries.is_empty() || bytes < http.batch_bytes)
+            {
+                let Some((_, entry)) = state.queue.pop_front() else {
+                    break;
+                };
+
+                bytes += approximate_len(&entry);
+                entries.push(entry);
+            }
+
+            state.bytes = state.bytes.saturating_sub(bytes);
+            state.sending = true;
+
+            if state.dropped > 0 {
+                entries.push(dropped_warning(std::mem::take(&mut state.dropped)));
+            }
+
+            entries
+        };
+
+        let body = write_body(&http.body, &entries, config);
+
+        let mut backoff = http.min_backoff;
+        let mut attempt = 0;
+
+        let delivered = loop {
+            match post(url, http, &body) {
+                Delivery::Delivered => break true,
+                Delivery::Rejected => break false,
+                Delivery::Retry if attempt >= http.retries => break false,
+                Delivery::Retry => {}
+            }
+
+            attempt += 1;
+
+            let state = shared.state.lock().unwrap();
+            let (state, _) = shared
+                .changed
+                .wait_timeout_while(state, backoff, |state| !state.shutdown)
+                .unwrap();
+
+            if state.shutdown {
+                break post(url, http, &body) == Delivery::Delivered;
+            }
+
+            backoff = (backoff * 2).min(http.max_backoff);
+        };
+
+        if !delivered {
+            shared.write_dead_letter(&body);
+        }
+
+        shared.state.lock().unwrap().sending = false;
+        shared.changed.notify_all();
+    }
+}
+
+impl Log for HttpLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = OwnedRecord::new(record);
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.bytes += approximate_len(&entry);
+        state.queue.push_back((Instant::now(), entry));
+
+        let mut dropped = Vec::new();
+
+        while state.queue.len() > self.capacity {
+            if let Some((_, entry)) = state.queue.pop_front() {
+                state.bytes = state.bytes.saturating_sub(approximate_len(&entry));
+                dropped.push(entry);
+            }
+        }
+
+        if !dropped.is_empty() && self.has_dead_letter {
+            drop(state);
+            self.shared
+                .write_dead_letter(&write_body(&self.body, &dropped, &self.config));
+        } else {
+            state.dropped += dropped.len();
+            drop(state);
+        }
+
+        self.shared.changed.notify_all();
+    }
+
+    /// Send the buffered records and wait until they are delivered or written to the dead-letter
+    /// file.
+    fn flush(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.flush = true;
+        self.shared.changed.notify_all();
+
+        let _ = self
+            .shared
+            .changed
+            .wait_timeout_while(state, FLUSH_TIMEOUT, |state| {
+                state.sending || !state.queue.is_empty()
+            })
+            .unwrap();
+    }
+}
+
+impl Drop for HttpLogger {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use log::{
+    kv::{Key, Value, VisitSource},
+    Log,
+};
+use std::{
+    fs::File,
+    io::Write,
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        unix::{ffi::OsStrExt, net::UnixDatagram},
+    },
+};
+
+use crate::{drivers::syslog::severity, error::FtailError, Config};
+
+/// The fields set by the driver, which key-values can't override.
+const RESERVED_FIELDS: [&str; 7] = [
+    "MESSAGE",
+    "PRIORITY",
+    "SYSLOG_IDENTIFIER",
+    "TARGET",
+    "CODE_FILE",
+    "CODE_LINE",
+    "CODE_MODULE",
+];
+
+/// The configuration of the `journald` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Journald {
+    socket: String,
+    syslog_identifier: Option<String>,
+}
+
+impl Journald {
+    /// Send the messages to the native socket of journald at `/run/systemd/journal/socket`.
+    pub fn new() -> Self {
+        Journald {
+            socket: "/run/systemd/journal/socket".to_string(),
+            syslog_identifier: None,
+        }
+    }
+
+    /// Send the messages to another socket.
+    pub fn socket(mut self, socket: &str) -> Self {
+        self.socket = socket.to_string();
+
+        self
+    }
+
+    /// Set the `SYSLOG_IDENTIFIER` field, the name of the executable by default.
+    pub fn syslog_identifier(mut self, syslog_identifier: &str) -> Self {
+        self.syslog_identifier = Some(syslog_identifier.to_string());
+
+        self
+    }
+}
+
+impl Default for Journald {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A logger that sends messages as structured fields to the native protocol socket of journald.
+pub struct JournaldLogger {
+    socket: UnixDatagram,
+    path: String,
+    syslog_identifier: String,
+    config: Config,
+}
+
+impl JournaldLogger {
+    pub fn new(journald: Journald, config: Config) -> Result<Self, FtailError> {
+        std::fs::metadata(&journald.socket).map_err(FtailError::IoError)?;
+
+        let socket = UnixDatagram::unbound().map_err(FtailError::IoError)?;
+
+        let syslog_identifier = journald.syslog_identifier.unwrap_or_else(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+                .unwrap_or_default()
+        });
+
+        Ok(JournaldLogger {
+            socket,
+            path: journald.socket,
+            syslog_identifier,
+            config,
+        })
+    }
+
+    fn format(&self, record: &log::Record) -> Vec<u8> {
+        let mut fields = Fields(Vec::new());
+
+        fields.add("MESSAGE", &record.args().to_string());
+        fields.add("PRIORITY", &severity(record.level()).to_string());
+
+        if !self.syslog_identifier.is_empty() {
+            fields.add("SYSLOG_IDENTIFIER", &self.syslog_identifier);
+        }
+
+        fields.add("TARGET", record.target());
+
+        if let Some(file) = record.file() {
+            fields.add("CODE_FILE", file);
+        }
+
+        if let Some(line) = record.line() {
+            fields.add("CODE_LINE", &line.to_string());
+        }
+
+        if let Some(module_path) = record.module_path() {
+            fields.add("CODE_MODULE", module_path);
+        }
+
+        let _ = record.key_values().visit(&mut fields);
+
+        fields.0
+    }
+
+    /// Send the entry as a datagram, or through a sealed memory file when it's too large.
+    fn send(&self, entry: &[u8]) -> std::io::Result<()> {
+        match self.socket.send_to(entry, &self.path) {
+            Err(error) if matches!(error.raw_os_error(), Some(libc::EMSGSIZE | libc::ENOBUFS)) => {
+                self.send_memfd(entry)
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Write the entry to a sealed memfd and pass its file descriptor with `SCM_RIGHTS`, like
+    /// `sd_journal_send` does for large entries.
+    fn send_memfd(&self, entry: &[u8]) -> std::io::Result<()> {
+        let fd = unsafe {
+            libc::memfd_create(
+                c"ftail-journald".as_ptr(),
+                libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+            )
+        };
+
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.write_all(entry)?;
+
+        let seals =
+            libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+
+        if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut address: libc::sockaddr_un = unsafe { std::mem::zeroed() };
+        address.sun_family = libc::AF_UNIX as libc::sa_family_t;
+
+        let path = std::path::Path::new(&self.path).as_os_str().as_bytes();
+
+        if path.len() >= address.sun_path.len() {
+            return Err(std::io::Error::from(std::io::ErrorKind::InvalidInput));
+        }
+
+        for (dst, src) in address.sun_path.iter_mut().zip(path) {
+            *dst = *src as libc::c_char;
+        }
+
+        // aligned for the `cmsghdr`
+        let mut control = [0u64; 8];
+        let control_len = unsafe { libc::CMSG_SPACE(std::mem::size_of::<libc::c_int>() as u32) };
+
+        let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+        message.msg_name = (&mut address as *mut libc::sockaddr_un).cast();
+        message.msg_namelen = std::mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+        message.msg_control = control.as_mut_ptr().cast();
+        message.msg_controllen = control_len as _;
+
+        unsafe {
+            let header = libc::CMSG_FIRSTHDR(&message);
+            (*header).cmsg_level = libc::SOL_SOCKET;
+            (*header).cmsg_type = libc::SCM_RIGHTS;
+            (*header).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<libc::c_int>() as u32) as _;
+            std::ptr::write_unaligned(libc::CMSG_DATA(header).cast::<libc::c_int>(), fd);
+        }
+
+        if unsafe { libc::sendmsg(self.socket.as_raw_fd(), &message, libc::MSG_NOSIGNAL) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// The fields of a journal entry in the native protocol.
+struct Fields(Vec<u8>);
+
+impl Fields {
+    fn add(&mut self, name: &str, value: &str) {
+        self.0.extend_from_slice(name.as_bytes());
+
+        // values with newlines are prefixed with their length instead
+        if value.contains('\n') {
+            self.0.push(b'\n');
+            self.0
+                .extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            self.0.push(b'=');
+        }
+
+        self.0.extend_from_slice(value.as_bytes());
+        self.0.push(b'\n');
+    }
+}
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        match field_name(key.as_str()) {
+            Some(name) if !RESERVED_FIELDS.contains(&name.as_str()) => {
+                self.add(&name, &value.to_string())
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Convert a key to a journal field name, which consists of uppercase letters, digits and
+/// underscores and doesn't start with an underscore or digit.
+fn field_name(key: &str) -> Option<String> {
+    let name: String = key
+        .chars()
+        .map(|c| match c {
+            'a'..='z' => c.to_ascii_uppercase(),
+            'A'..='Z' | '0'..='9' => c,
+            _ => '_',
+        })
+        .skip_while(|c| *c == '_' || c.is_ascii_digit())
+        .collect();
+
+    (!name.is_empty()).then_some(name)
+}
+
+impl Log for JournaldLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // messages that can't be sent, e.g. when journald is restarting, are dropped
+        let _ = self.send(&self.format(record));
+    }
+
+    fn flush(&self) {}
+}
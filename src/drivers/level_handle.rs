@@ -0,0 +1,35 @@
+use log::Log;
+
+use crate::level::LevelHandle;
+
+/// A logger that passes the messages to the wrapped driver if they match the level of the
+/// handle, so the level can be changed at runtime.
+pub struct LevelHandleLogger {
+    driver: Box<dyn Log + Send + Sync>,
+    handle: LevelHandle,
+}
+
+impl LevelHandleLogger {
+    /// The wrapped driver should accept all levels.
+    pub fn new(driver: Box<dyn Log + Send + Sync>, handle: LevelHandle) -> Self {
+        LevelHandleLogger { driver, handle }
+    }
+}
+
+impl Log for LevelHandleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.handle.is_enabled(metadata.level()) && self.driver.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.driver.log(record);
+    }
+
+    fn flush(&self) {
+        self.driver.flush();
+    }
+}
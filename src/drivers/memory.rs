@@ -0,0 +1,140 @@
+use log::Log;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    formatters::{default::DefaultFormatter, Formatter},
+    Config,
+};
+
+/// The maximum amount of records kept by a [`MemoryBuffer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capacity {
+    /// Keep the last `N` records.
+    Records(usize),
+    /// Keep the last records that fit in `N` bytes. Records larger than `N` are dropped.
+    Bytes(usize),
+}
+
+/// A handle to the records kept in memory by the `memory` driver.
+///
+/// The handle can be cloned and shared, e.g. with a diagnostics endpoint.
+#[derive(Clone)]
+pub struct MemoryBuffer {
+    inner: Arc<Mutex<RingBuffer>>,
+}
+
+struct RingBuffer {
+    records: VecDeque<String>,
+    capacity: Capacity,
+    bytes: usize,
+}
+
+impl MemoryBuffer {
+    /// Create a new buffer with the given capacity.
+    pub fn new(capacity: Capacity) -> Self {
+        MemoryBuffer {
+            inner: Arc::new(Mutex::new(RingBuffer {
+                records: VecDeque::new(),
+                capacity,
+                bytes: 0,
+            })),
+        }
+    }
+
+    /// Return a copy of the buffered records, oldest first.
+    pub fn snapshot(&self) -> Vec<String> {
+        self.inner.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    /// Remove and return the buffered records, oldest first.
+    pub fn drain(&self) -> Vec<String> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.bytes = 0;
+
+        inner.records.drain(..).collect()
+    }
+
+    /// Remove all buffered records.
+    pub fn clear(&self) {
+        self.drain();
+    }
+
+    /// The number of buffered records.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().records.len()
+    }
+
+    /// Whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(&self, record: String) {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.capacity {
+            Capacity::Records(max) => {
+                if max == 0 {
+                    return;
+                }
+
+                while inner.records.len() >= max {
+                    inner.pop_front();
+                }
+            }
+            Capacity::Bytes(max) => {
+                if record.len() > max {
+                    return;
+                }
+
+                while inner.bytes + record.len() > max {
+                    inner.pop_front();
+                }
+            }
+        }
+
+        inner.bytes += record.len();
+        inner.records.push_back(record);
+    }
+}
+
+impl RingBuffer {
+    fn pop_front(&mut self) {
+        if let Some(record) = self.records.pop_front() {
+            self.bytes -= record.len();
+        }
+    }
+}
+
+/// A logger that keeps the last formatted messages in memory.
+pub struct MemoryLogger {
+    buffer: MemoryBuffer,
+    config: Config,
+}
+
+impl MemoryLogger {
+    pub fn new(buffer: MemoryBuffer, config: Config) -> Self {
+        MemoryLogger { buffer, config }
+    }
+}
+
+impl Log for MemoryLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = DefaultFormatter::new(record, &self.config);
+
+        self.buffer.push(formatter.format());
+    }
+
+    fn flush(&self) {}
+}
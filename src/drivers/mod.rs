@@ -1,6 +1,24 @@
+pub mod callback;
+#[cfg(feature = "testing")]
+pub mod capture;
 pub mod console;
 pub mod daily_file;
+pub mod error_context;
+pub mod filter;
 pub mod formatted_console;
+pub mod gelf;
+pub mod http;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub mod journald;
+pub mod level_handle;
+pub mod memory;
+pub mod otlp;
+pub mod rate_limit;
+pub mod sampling;
 pub mod single_file;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stream;
+pub mod syslog;
 #[cfg(test)]
 pub mod test;
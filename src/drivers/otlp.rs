@@ -0,0 +1,128 @@
+use log::Log;
+use std::{
+    fs::File,
+    io::{LineWriter, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use crate::{
+    context::hostname,
+    error::FtailError,
+    formatters::otlp::write_request,
+    helpers::{
+        lock_file_path, open_append, reopen_if_moved, rotate_if_exceeds_max_file_size, FileLock,
+    },
+    record::OwnedRecord,
+    Config,
+};
+
+/// The resource of the OpenTelemetry log records, i.e. the service that logs them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Otlp {
+    service_name: String,
+    host_name: Option<String>,
+    attributes: Vec<(String, String)>,
+}
+
+impl Otlp {
+    /// Create a resource with the `service.name` attribute.
+    pub fn new(service_name: &str) -> Self {
+        Otlp {
+            service_name: service_name.to_string(),
+            host_name: None,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Set the `host.name` attribute, the hostname of the machine by default.
+    pub fn host_name(mut self, host_name: &str) -> Self {
+        self.host_name = Some(host_name.to_string());
+
+        self
+    }
+
+    /// Add a resource attribute, e.g. `service.version` or `deployment.environment`.
+    pub fn attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes.push((key.to_string(), value.to_string()));
+
+        self
+    }
+
+    pub(crate) fn resource_attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        [
+            ("service.name", self.service_name.as_str()),
+            ("host.name", self.host_name.as_deref().unwrap_or(hostname())),
+        ]
+        .into_iter()
+        .chain(
+            self.attributes
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
+    }
+}
+
+/// A logger that writes the records as OTLP/JSON lines to a file, e.g. for the `otlpjsonfile`
+/// receiver of the OpenTelemetry Collector.
+pub struct OtlpFileLogger {
+    file: Mutex<LineWriter<File>>,
+    file_path: PathBuf,
+    otlp: Otlp,
+    config: Config,
+}
+
+impl OtlpFileLogger {
+    pub fn new(path: &str, otlp: Otlp, config: Config) -> Result<Self, FtailError> {
+        let file = open_append(&PathBuf::from(path)).map_err(FtailError::IoError)?;
+
+        let md = std::fs::metadata(path).map_err(FtailError::IoError)?;
+
+        if md.permissions().readonly() {
+            return Err(FtailError::PermissionsError(path.to_string()));
+        }
+
+        Ok(OtlpFileLogger {
+            file: Mutex::new(LineWriter::new(file)),
+            file_path: PathBuf::from(path),
+            otlp,
+            config,
+        })
+    }
+}
+
+impl Log for OtlpFileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut line = String::new();
+        write_request(&mut line, &self.otlp, &[OwnedRecord::new(record)]).unwrap();
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+
+        let _lock = if self.config.multi_process {
+            let lock = FileLock::acquire(&lock_file_path(&self.file_path)).unwrap();
+            reopen_if_moved(&mut file, &self.file_path);
+
+            Some(lock)
+        } else {
+            None
+        };
+
+        rotate_if_exceeds_max_file_size(&mut file, &self.file_path, &self.config);
+
+        file.write_all(line.as_bytes()).unwrap();
+        file.flush().unwrap();
+    }
+
+    fn flush(&self) {
+        self.file.lock().unwrap().flush().unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+use log::Log;
+
+use crate::rate_limit::RateLimiter;
+
+/// A logger that applies a rate limit and collapses consecutive identical messages before
+/// passing them to the wrapped driver.
+pub struct RateLimitLogger {
+    driver: Box<dyn Log + Send + Sync>,
+    rate_limiter: RateLimiter,
+}
+
+impl RateLimitLogger {
+    pub(crate) fn new(driver: Box<dyn Log + Send + Sync>, rate_limiter: RateLimiter) -> Self {
+        RateLimitLogger {
+            driver,
+            rate_limiter,
+        }
+    }
+}
+
+impl Log for RateLimitLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.driver.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.rate_limiter
+            .log(record, |record| self.driver.log(record));
+    }
+
+    fn flush(&self) {
+        self.rate_limiter.flush(|record| self.driver.log(record));
+        self.driver.flush();
+    }
+}
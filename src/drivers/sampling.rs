@@ -0,0 +1,33 @@
+use log::Log;
+
+use crate::sampling::Sampler;
+
+/// A logger that only passes a sample of the messages to the wrapped driver.
+pub struct SamplingLogger {
+    driver: Box<dyn Log + Send + Sync>,
+    sampler: Sampler,
+}
+
+impl SamplingLogger {
+    pub(crate) fn new(driver: Box<dyn Log + Send + Sync>, sampler: Sampler) -> Self {
+        SamplingLogger { driver, sampler }
+    }
+}
+
+impl Log for SamplingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.driver.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) || !self.sampler.sample(record.metadata()) {
+            return;
+        }
+
+        self.driver.log(record);
+    }
+
+    fn flush(&self) {
+        self.driver.flush();
+    }
+}
@@ -1,4 +1,4 @@
-use log::{LevelFilter, Log};
+use log::Log;
 use std::{
     fs::File,
     io::{LineWriter, Write},
@@ -8,8 +8,10 @@ use std::{
 
 use crate::{
     error::FtailError,
-    formatters::{default::DefaultFormatter, Formatter},
-    helpers::rotate_if_exceeds_max_file_size,
+    helpers::{
+        format_file_line, lock_file_path, reopen_if_moved, rotate_if_exceeds_max_file_size,
+        FileLock,
+    },
     Config,
 };
 
@@ -25,7 +27,7 @@ impl SingleFileLogger {
         let file = std::fs::OpenOptions::new()
             .create(true)
             .write(true)
-            .append(append)
+            .append(append || config.multi_process)
             .open(path)
             .map_err(FtailError::IoError)?;
 
@@ -45,11 +47,7 @@ impl SingleFileLogger {
 
 impl Log for SingleFileLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
-        metadata.level() <= self.config.level_filter
+        self.config.is_enabled(metadata.level())
     }
 
     fn log(&self, record: &log::Record) {
@@ -57,12 +55,22 @@ impl Log for SingleFileLogger {
             return;
         }
 
-        rotate_if_exceeds_max_file_size(&self.file, self.file_path.clone(), &self.config);
+        let mut file = self.file.lock().unwrap();
+
+        let _lock = if self.config.multi_process {
+            let lock = FileLock::acquire(&lock_file_path(&self.file_path)).unwrap();
+            reopen_if_moved(&mut file, &self.file_path);
 
-        let formatter = DefaultFormatter::new(record, &self.config);
+            Some(lock)
+        } else {
+            None
+        };
 
-        let mut file = self.file.lock().unwrap();
-        writeln!(file, "{}", formatter.format()).unwrap();
+        rotate_if_exceeds_max_file_size(&mut file, &self.file_path, &self.config);
+
+        format_file_line(record, &self.config, |line| {
+            file.write_all(line.as_bytes()).unwrap()
+        });
         file.flush().unwrap();
     }
 
@@ -0,0 +1,373 @@
+use log::Log;
+use rusqlite::{params, Connection};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{error::FtailError, record::OwnedRecord, Config};
+
+/// How long `flush` waits for the buffered records to be inserted.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the retention policy deletes old records.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The timestamps are stored in UTC with a fixed width, so they sort as text and work with the
+/// date and time functions of SQLite.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6fZ";
+
+/// The configuration of the `sqlite` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sqlite {
+    path: String,
+    batch_size: usize,
+    batch_interval: Duration,
+    capacity: usize,
+    max_age: Option<Duration>,
+    max_rows: Option<u64>,
+}
+
+impl Sqlite {
+    /// Insert the records into the `logs` table of a database file, which is created if it
+    /// doesn't exist.
+    pub fn new(path: &str) -> Self {
+        Sqlite {
+            path: path.to_string(),
+            batch_size: 100,
+            batch_interval: Duration::from_secs(1),
+            capacity: 10_000,
+            max_age: None,
+            max_rows: None,
+        }
+    }
+
+    /// Set the number of records inserted per transaction, 100 by default.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+
+        self
+    }
+
+    /// Set how long a record may wait for the batch to fill up, 1 second by default.
+    pub fn batch_interval(mut self, batch_interval: Duration) -> Self {
+        self.batch_interval = batch_interval;
+
+        self
+    }
+
+    /// Set the number of records kept in memory while the database is busy, 10 000 by default.
+    /// The oldest records are dropped when the buffer is full.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+
+        self
+    }
+
+    /// Delete records older than the maximum age.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+
+        self
+    }
+
+    /// Delete the oldest records when the table has more rows.
+    pub fn max_rows(mut self, max_rows: u64) -> Self {
+        self.max_rows = Some(max_rows);
+
+        self
+    }
+}
+
+fn open(path: &str) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+
+    connection.pragma_update(None, "journal_mode", "WAL")?;
+    connection.pragma_update(None, "synchronous", "NORMAL")?;
+    connection.busy_timeout(Duration::from_secs(5))?;
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS logs (
+            id INTEGER PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            level TEXT NOT NULL,
+            target TEXT NOT NULL,
+            message TEXT NOT NULL,
+            file TEXT,
+            line INTEGER,
+            key_values TEXT
+        );
+        CREATE INDEX IF NOT EXISTS logs_timestamp ON logs (timestamp);",
+    )?;
+
+    Ok(connection)
+}
+
+fn insert(connection: &mut Connection, entries: &[OwnedRecord]) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+
+    {
+        let mut statement = transaction.prepare_cached(
+            "INSERT INTO logs (timestamp, level, target, message, file, line, key_values)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+
+        for entry in entries {
+            statement.execute(params![
+                entry
+                    .datetime
+                    .naive_utc()
+                    .format(TIMESTAMP_FORMAT)
+                    .to_string(),
+                entry.level.as_str(),
+                entry.target,
+                entry.message,
+                entry.file,
+                entry.line,
+                key_values(entry),
+            ])?;
+        }
+    }
+
+    transaction.commit()
+}
+
+/// The key-values as a JSON object, which can be queried with `json_extract`.
+fn key_values(entry: &OwnedRecord) -> Option<String> {
+    if entry.key_values.is_empty() {
+        return None;
+    }
+
+    let mut result = String::from("{");
+
+    for (i, (key, value)) in entry.key_values.iter().enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+
+        crate::json::write_string(&mut result, key).unwrap();
+        result.push(':');
+        value.write_json(&mut result).unwrap();
+    }
+
+    result.push('}');
+
+    Some(result)
+}
+
+fn apply_retention(connection: &Connection, sqlite: &Sqlite) -> rusqlite::Result<()> {
+    // an age beyond the range of dates keeps every record
+    let cutoff = sqlite.max_age.and_then(|max_age| {
+        chrono::Utc::now().checked_sub_signed(chrono::Duration::from_std(max_age).ok()?)
+    });
+
+    if let Some(cutoff) = cutoff {
+        connection.execute(
+            "DELETE FROM logs WHERE timestamp < ?1",
+            params![cutoff.format(TIMESTAMP_FORMAT).to_string()],
+        )?;
+    }
+
+    if let Some(max_rows) = sqlite.max_rows {
+        connection.execute(
+            "DELETE FROM logs WHERE id <= (SELECT max(id) FROM logs) - ?1",
+            params![max_rows.min(i64::MAX as u64) as i64],
+        )?;
+    }
+
+    Ok(())
+}
+
+struct State {
+    /// The records with the time they were queued.
+    queue: VecDeque<(Instant, OwnedRecord)>,
+    flush: bool,
+    inserting: bool,
+    shutdown: bool,
+}
+
+impl State {
+    /// When the oldest record in the queue was queued.
+    fn oldest(&self) -> Option<Instant> {
+        self.queue.front().map(|(queued, _)| *queued)
+    }
+
+    /// Whether a batch is full or its oldest record has waited for the batch interval.
+    fn is_due(&self, sqlite: &Sqlite) -> bool {
+        self.queue.len() >= sqlite.batch_size
+            || self
+                .oldest()
+                .is_some_and(|oldest| oldest.elapsed() >= sqlite.batch_interval)
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// A logger that inserts batches of records into a SQLite database from a background thread.
+pub struct SqliteLogger {
+    shared: Arc<Shared>,
+    capacity: usize,
+    worker: Option<JoinHandle<()>>,
+    config: Config,
+}
+
+impl SqliteLogger {
+    pub fn new(sqlite: Sqlite, config: Config) -> Result<Self, FtailError> {
+        let connection = open(&sqlite.path).map_err(FtailError::SqliteError)?;
+        apply_retention(&connection, &sqlite).map_err(FtailError::SqliteError)?;
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                flush: false,
+                inserting: false,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let capacity = sqlite.capacity;
+
+        let worker = {
+            let shared = shared.clone();
+
+            std::thread::Builder::new()
+                .name("ftail-sqlite".to_string())
+                .spawn(move || run(&shared, &sqlite, connection))
+                .map_err(FtailError::IoError)?
+        };
+
+        Ok(SqliteLogger {
+            shared,
+            capacity,
+            worker: Some(worker),
+            config,
+        })
+    }
+}
+
+/// Insert a batch once it's full or the oldest record has waited for the batch interval, and
+/// apply the retention policy every retention interval, even when nothing is logged.
+fn run(shared: &Shared, sqlite: &Sqlite, mut connection: Connection) {
+    let mut retained = Instant::now();
+
+    loop {
+        let entries = {
+            let mut state = shared.state.lock().unwrap();
+
+            loop {
+                if !state.queue.is_empty()
+                    && (state.is_due(sqlite) || state.flush || state.shutdown)
+                {
+                    break;
+                }
+
+                if state.shutdown {
+                    return;
+                }
+
+                state.flush = false;
+
+                if retained.elapsed() >= RETENTION_INTERVAL {
+                    break;
+                }
+
+                let mut timeout = RETENTION_INTERVAL.saturating_sub(retained.elapsed());
+
+                if let Some(oldest) = state.oldest() {
+                    timeout = timeout.min(sqlite.batch_interval.saturating_sub(oldest.elapsed()));
+                }
+
+                state = shared.changed.wait_timeout(state, timeout).unwrap().0;
+            }
+
+            // a flush or shutdown inserts everything in one transaction
+            let len = if state.flush || state.shutdown {
+                state.queue.len()
+            } else if state.is_due(sqlite) {
+                state.queue.len().min(sqlite.batch_size)
+            } else {
+                // only the retention policy is due
+                0
+            };
+
+            let entries = state
+                .queue
+                .drain(..len)
+                .map(|(_, entry)| entry)
+                .collect::<Vec<_>>();
+
+            state.inserting = true;
+
+            entries
+        };
+
+        // records that can't be inserted, e.g. when the disk is full, are dropped
+        if !entries.is_empty() {
+            let _ = insert(&mut connection, &entries);
+        }
+
+        if retained.elapsed() >= RETENTION_INTERVAL {
+            let _ = apply_retention(&connection, sqlite);
+            retained = Instant::now();
+        }
+
+        shared.state.lock().unwrap().inserting = false;
+        shared.changed.notify_all();
+    }
+}
+
+impl Log for SqliteLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = OwnedRecord::new(record);
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.queue.push_back((Instant::now(), entry));
+
+        while state.queue.len() > self.capacity {
+            state.queue.pop_front();
+        }
+
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+
+    /// Insert the buffered records and wait until they are committed.
+    fn flush(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.flush = true;
+        self.shared.changed.notify_all();
+
+        let _ = self
+            .shared
+            .changed
+            .wait_timeout_while(state, FLUSH_TIMEOUT, |state| {
+                state.inserting || !state.queue.is_empty()
+            })
+            .unwrap();
+    }
+}
+
+impl Drop for SqliteLogger {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use log::Log;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    net::TcpStream,
+    sync::{Arc, Condvar, Mutex},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use crate::{
+    error::FtailError,
+    helpers::{connect_tcp, format_file_line},
+    Config,
+};
+
+/// How long a write may take before the peer is considered stalled and the driver reconnects.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long `flush` waits for the buffered records to be sent.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How much of the spill file is read into memory at once when it's replayed.
+const SPILL_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where the `stream` driver sends the records.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamEndpoint {
+    /// A TCP endpoint, e.g. `127.0.0.1:5170`.
+    Tcp(String),
+    /// A Unix stream socket, e.g. `/run/collector.sock`.
+    #[cfg(unix)]
+    Unix(String),
+}
+
+/// The configuration of the `stream` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stream {
+    endpoint: StreamEndpoint,
+    capacity: usize,
+    spill_path: Option<String>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Stream {
+    /// Stream the records to a TCP endpoint.
+    pub fn tcp(address: &str) -> Self {
+        Self::new(StreamEndpoint::Tcp(address.to_string()))
+    }
+
+    /// Stream the records to a Unix stream socket.
+    #[cfg(unix)]
+    pub fn unix(path: &str) -> Self {
+        Self::new(StreamEndpoint::Unix(path.to_string()))
+    }
+
+    fn new(endpoint: StreamEndpoint) -> Self {
+        Stream {
+            endpoint,
+            capacity: 10_000,
+            spill_path: None,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Set the number of records kept in memory while the peer is unavailable, 10 000 by
+    /// default. The oldest records are dropped, or spilled to disk, when the buffer is full.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+
+        self
+    }
+
+    /// Append the records that don't fit in the buffer to a file, and send them once the peer
+    /// is available again.
+    pub fn spill_to_disk(mut self, path: &str) -> Self {
+        self.spill_path = Some(path.to_string());
+
+        self
+    }
+
+    /// Set the delay before the first reconnect attempt, which doubles after every failed
+    /// attempt up to `max`. 100 milliseconds and 30 seconds by default.
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max;
+
+        self
+    }
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    fn connect(endpoint: &StreamEndpoint) -> std::io::Result<Connection> {
+        match endpoint {
+            StreamEndpoint::Tcp(address) => {
+                Ok(Connection::Tcp(connect_tcp(address, WRITE_TIMEOUT)?))
+            }
+            #[cfg(unix)]
+            StreamEndpoint::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+                Ok(Connection::Unix(stream))
+            }
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.write_all(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write_all(buf),
+        }
+    }
+}
+
+struct State {
+    queue: VecDeque<String>,
+    spill: Option<File>,
+    spilled: bool,
+    /// How much of the spill file has been sent.
+    spill_offset: u64,
+    sending: bool,
+    connected: bool,
+    shutdown: bool,
+}
+
+impl State {
+    /// Drop, or spill to disk, the oldest records that exceed the capacity.
+    fn trim(&mut self, capacity: usize) {
+        while self.queue.len() > capacity {
+            let record = self.queue.pop_front().unwrap();
+
+            if let Some(spill) = self.spill.as_mut() {
+                if spill.write_all(record.as_bytes()).is_ok() {
+                    self.spilled = true;
+                }
+            }
+        }
+    }
+
+    /// Read the next chunk of the spill file that hasn't been sent. Records spilled meanwhile
+    /// are appended, so the file is replayed in order.
+    fn read_spilled(&mut self) -> Vec<u8> {
+        let mut chunk = Vec::new();
+
+        if let Some(spill) = self.spill.as_mut() {
+            if spill.seek(SeekFrom::Start(self.spill_offset)).is_ok() {
+                let _ = spill.take(SPILL_CHUNK_SIZE as u64).read_to_end(&mut chunk);
+            }
+        }
+
+        if chunk.is_empty() {
+            self.clear_spilled();
+        }
+
+        chunk
+    }
+
+    /// Mark a chunk as sent, and empty the file once all of it is sent.
+    fn consume_spilled(&mut self, len: usize) {
+        self.spill_offset += len as u64;
+
+        let spill_len = self
+            .spill
+            .as_ref()
+            .and_then(|spill| spill.metadata().ok())
+            .map_or(0, |metadata| metadata.len());
+
+        if self.spill_offset >= spill_len {
+            self.clear_spilled();
+        }
+    }
+
+    fn clear_spilled(&mut self) {
+        if let Some(spill) = self.spill.as_mut() {
+            let _ = spill.set_len(0);
+        }
+
+        self.spill_offset = 0;
+        self.spilled = false;
+    }
+}
+
+/// What the worker sends next: the spilled records come before the buffered ones.
+enum Batch {
+    Spilled(Vec<u8>),
+    Records(VecDeque<String>),
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// A logger that streams formatted records to a TCP or Unix socket from a background thread.
+pub struct StreamLogger {
+    shared: Arc<Shared>,
+    capacity: usize,
+    worker: Option<JoinHandle<()>>,
+    config: Config,
+}
+
+impl StreamLogger {
+    pub fn new(stream: Stream, config: Config) -> Result<Self, FtailError> {
+        let spill = match &stream.spill_path {
+            Some(path) => Some(
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .read(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(FtailError::IoError)?,
+            ),
+            None => None,
+        };
+
+        let spilled = match &spill {
+            Some(spill) => spill.metadata().map_err(FtailError::IoError)?.len() > 0,
+            None => false,
+        };
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                spill,
+                spilled,
+                spill_offset: 0,
+                sending: false,
+                connected: false,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let capacity = stream.capacity;
+
+        let worker = {
+            let shared = shared.clone();
+
+            std::thread::Builder::new()
+                .name("ftail-stream".to_string())
+                .spawn(move || run(&shared, &stream))
+                .map_err(FtailError::IoError)?
+        };
+
+        Ok(StreamLogger {
+            shared,
+            capacity,
+            worker: Some(worker),
+            config,
+        })
+    }
+}
+
+/// Send the buffered records, reconnecting with exponential backoff when the peer is unavailable.
+fn run(shared: &Shared, stream: &Stream) {
+    let mut connection = None;
+    let mut backoff = stream.min_backoff;
+
+    loop {
+        if connection.is_none() {
+            match Connection::connect(&stream.endpoint) {
+                Ok(c) => {
+                    connection = Some(c);
+                    backoff = stream.min_backoff;
+                    shared.state.lock().unwrap().connected = true;
+                }
+                Err(_) => {
+                    let state = shared.state.lock().unwrap();
+                    let (mut state, _) = shared
+                        .changed
+                        .wait_timeout_while(state, backoff, |state| !state.shutdown)
+                        .unwrap();
+
+                    if state.shutdown {
+                        // keep the buffered records for the next start
+                        state.trim(0);
+
+                        return;
+                    }
+
+                    backoff = (backoff * 2).min(stream.max_backoff);
+
+                    continue;
+                }
+            }
+        }
+
+        let mut batch = {
+            let mut state = shared
+                .changed
+                .wait_while(shared.state.lock().unwrap(), |state| {
+                    state.queue.is_empty() && !state.spilled && !state.shutdown
+                })
+                .unwrap();
+
+            if state.shutdown && state.queue.is_empty() && !state.spilled {
+                return;
+            }
+
+            state.sending = true;
+
+            if state.spilled {
+                Batch::Spilled(state.read_spilled())
+            } else {
+                Batch::Records(state.queue.drain(..).collect())
+            }
+        };
+
+        let connected = connection.as_mut().unwrap();
+
+        let failed = match &mut batch {
+            Batch::Spilled(chunk) => connected.write_all(chunk).is_err(),
+            Batch::Records(records) => {
+                while let Some(record) = records.front() {
+                    if connected.write_all(record.as_bytes()).is_err() {
+                        break;
+                    }
+
+                    records.pop_front();
+                }
+
+                !records.is_empty()
+            }
+        };
+
+        let mut state = shared.state.lock().unwrap();
+        state.sending = false;
+
+        match batch {
+            Batch::Spilled(chunk) if !failed => state.consume_spilled(chunk.len()),
+            Batch::Spilled(_) => {}
+            Batch::Records(mut records) => {
+                while let Some(record) = records.pop_back() {
+                    state.queue.push_front(record);
+                }
+            }
+        }
+
+        let shutdown = state.shutdown && failed;
+
+        if failed {
+            connection = None;
+            state.connected = false;
+
+            // keep the buffered records for the next start when shutting down
+            state.trim(if shutdown { 0 } else { stream.capacity });
+        }
+
+        drop(state);
+        shared.changed.notify_all();
+
+        if shutdown {
+            return;
+        }
+    }
+}
+
+impl Log for StreamLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_file_line(record, &self.config, str::to_string);
+
+        let mut state = self.shared.state.lock().unwrap();
+        state.queue.push_back(line);
+        state.trim(self.capacity);
+
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+
+    /// Wait until the buffered records are sent. When the peer is unavailable, the buffered
+    /// records are spilled to disk instead, since a logger set with `init()` is never dropped.
+    fn flush(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if !state.connected {
+            if state.spill.is_some() {
+                state.trim(0);
+            }
+
+            return;
+        }
+
+        let _ = self
+            .shared
+            .changed
+            .wait_timeout_while(state, FLUSH_TIMEOUT, |state| {
+                state.connected && (state.sending || !state.queue.is_empty() || state.spilled)
+            })
+            .unwrap();
+    }
+}
+
+impl Drop for StreamLogger {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
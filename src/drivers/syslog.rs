@@ -0,0 +1,394 @@
+use log::{Level, Log};
+use std::{
+    fmt::Write as _,
+    io::Write,
+    net::{TcpStream, UdpSocket},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixDatagram, UnixStream};
+
+use crate::{
+    error::FtailError,
+    helpers::{connect_tcp, connect_udp},
+    timestamp::Timestamp,
+    writer::write_datetime,
+    Config,
+};
+
+/// The first and the longest wait before reconnecting after the daemon was unreachable.
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The message format of the `syslog` driver.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyslogFormat {
+    /// `<14>1 2024-09-17T19:37:22.402123+02:00 web-1 app 1234 db - message`
+    #[default]
+    Rfc5424,
+    /// `<14>Sep 17 19:37:22 web-1 app[1234]: message`
+    Rfc3164,
+}
+
+/// The syslog facility of the messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Facility {
+    Kern = 0,
+    #[default]
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    Authpriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Where the `syslog` driver sends the messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyslogTransport {
+    /// A Unix socket of the local syslog daemon, e.g. `/dev/log`.
+    #[cfg(unix)]
+    Unix(String),
+    /// A UDP endpoint, e.g. `127.0.0.1:514`.
+    Udp(String),
+    /// A TCP endpoint, e.g. `127.0.0.1:514`.
+    Tcp(String),
+}
+
+/// The configuration of the `syslog` driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Syslog {
+    transport: SyslogTransport,
+    format: SyslogFormat,
+    facility: Facility,
+    app_name: Option<String>,
+    hostname: Option<String>,
+    timeout: Duration,
+}
+
+impl Syslog {
+    /// Send the messages to the local syslog daemon at `/dev/log`.
+    #[cfg(unix)]
+    pub fn local() -> Self {
+        Self::unix("/dev/log")
+    }
+
+    /// Send the messages to a Unix socket.
+    #[cfg(unix)]
+    pub fn unix(path: &str) -> Self {
+        Self::new(SyslogTransport::Unix(path.to_string()))
+    }
+
+    /// Send the messages to a UDP endpoint.
+    pub fn udp(address: &str) -> Self {
+        Self::new(SyslogTransport::Udp(address.to_string()))
+    }
+
+    /// Send the messages to a TCP endpoint.
+    pub fn tcp(address: &str) -> Self {
+        Self::new(SyslogTransport::Tcp(address.to_string()))
+    }
+
+    fn new(transport: SyslogTransport) -> Self {
+        Syslog {
+            transport,
+            format: SyslogFormat::default(),
+            facility: Facility::default(),
+            app_name: None,
+            hostname: None,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Set the message format, RFC 5424 by default.
+    pub fn format(mut self, format: SyslogFormat) -> Self {
+        self.format = format;
+
+        self
+    }
+
+    /// Set the facility, `user` by default.
+    pub fn facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+
+        self
+    }
+
+    /// Set the app name, the name of the executable by default.
+    pub fn app_name(mut self, app_name: &str) -> Self {
+        self.app_name = Some(app_name.to_string());
+
+        self
+    }
+
+    /// Set the hostname, the hostname of the machine by default.
+    pub fn hostname(mut self, hostname: &str) -> Self {
+        self.hostname = Some(hostname.to_string());
+
+        self
+    }
+
+    /// Set how long connecting or sending may block the logging thread before the message is
+    /// dropped, 5 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+}
+
+enum Connection {
+    #[cfg(unix)]
+    UnixDatagram(UnixDatagram),
+    #[cfg(unix)]
+    UnixStream(UnixStream),
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+impl Connection {
+    fn connect(transport: &SyslogTransport, timeout: Duration) -> std::io::Result<Connection> {
+        match transport {
+            #[cfg(unix)]
+            SyslogTransport::Unix(path) => {
+                let socket = UnixDatagram::unbound()?;
+
+                match socket.connect(path) {
+                    Ok(()) => {
+                        socket.set_write_timeout(Some(timeout))?;
+
+                        Ok(Connection::UnixDatagram(socket))
+                    }
+                    // the daemon may listen on a stream socket instead
+                    Err(e) => {
+                        let stream = UnixStream::connect(path).map_err(|_| e)?;
+                        stream.set_write_timeout(Some(timeout))?;
+
+                        Ok(Connection::UnixStream(stream))
+                    }
+                }
+            }
+            SyslogTransport::Udp(address) => Ok(Connection::Udp(connect_udp(address)?)),
+            SyslogTransport::Tcp(address) => Ok(Connection::Tcp(connect_tcp(address, timeout)?)),
+        }
+    }
+
+    fn send(&mut self, message: &str, format: SyslogFormat) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Connection::UnixDatagram(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            #[cfg(unix)]
+            Connection::UnixStream(stream) => write_framed(stream, message, format),
+            Connection::Udp(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            Connection::Tcp(stream) => write_framed(stream, message, format),
+        }
+    }
+}
+
+/// Frame the message on a stream socket, with octet counting for RFC 5424 and a trailing newline
+/// for RFC 3164 (RFC 6587).
+fn write_framed(
+    stream: &mut impl Write,
+    message: &str,
+    format: SyslogFormat,
+) -> std::io::Result<()> {
+    match format {
+        SyslogFormat::Rfc5424 => write!(stream, "{} {}", message.len(), message)?,
+        SyslogFormat::Rfc3164 => writeln!(stream, "{}", message)?,
+    }
+
+    stream.flush()
+}
+
+/// The connection to the daemon, and when to reconnect after it was unreachable.
+struct Sender {
+    connection: Option<Connection>,
+    backoff: Duration,
+    retry_at: Option<Instant>,
+}
+
+/// A logger that sends messages to a syslog daemon.
+pub struct SyslogLogger {
+    syslog: Syslog,
+    app_name: String,
+    hostname: String,
+    sender: Mutex<Sender>,
+    config: Config,
+}
+
+impl SyslogLogger {
+    pub fn new(syslog: Syslog, config: Config) -> Result<Self, FtailError> {
+        let connection =
+            Connection::connect(&syslog.transport, syslog.timeout).map_err(FtailError::IoError)?;
+
+        let app_name = syslog.app_name.clone().unwrap_or_else(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "-".to_string())
+        });
+
+        let hostname = syslog
+            .hostname
+            .clone()
+            .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().into_owned());
+
+        Ok(SyslogLogger {
+            syslog,
+            app_name,
+            hostname,
+            sender: Mutex::new(Sender {
+                connection: Some(connection),
+                backoff: MIN_BACKOFF,
+                retry_at: None,
+            }),
+            config,
+        })
+    }
+
+    fn format(&self, record: &log::Record) -> String {
+        let priority = self.syslog.facility as u8 * 8 + severity(record.level());
+        let datetime = Timestamp::current().datetime;
+        let pid = std::process::id();
+
+        let mut message = String::new();
+
+        match self.syslog.format {
+            SyslogFormat::Rfc5424 => {
+                write!(message, "<{}>1 ", priority).unwrap();
+                write_datetime(
+                    datetime,
+                    &self.config,
+                    None,
+                    "%Y-%m-%dT%H:%M:%S%.6f%:z",
+                    &mut message,
+                )
+                .unwrap();
+                write!(
+                    message,
+                    " {} {} {} {} - {}",
+                    header_field(&self.hostname, 255),
+                    header_field(&self.app_name, 48),
+                    pid,
+                    header_field(record.target(), 32),
+                    record.args()
+                )
+                .unwrap();
+            }
+            SyslogFormat::Rfc3164 => {
+                write!(message, "<{}>", priority).unwrap();
+                write_datetime(datetime, &self.config, None, "%b %e %H:%M:%S", &mut message)
+                    .unwrap();
+                write!(
+                    message,
+                    " {} {}[{}]: {}",
+                    self.hostname,
+                    self.app_name,
+                    pid,
+                    record.args()
+                )
+                .unwrap();
+            }
+        }
+
+        message
+    }
+
+    /// Send the message, reconnecting if the connection was lost. After a failed reconnect, the
+    /// messages are dropped until the backoff has passed, so an unreachable daemon doesn't slow
+    /// down every log call.
+    fn send(&self, message: &str) {
+        let mut sender = self.sender.lock().unwrap();
+
+        if let Some(connection) = sender.connection.as_mut() {
+            if connection.send(message, self.syslog.format).is_ok() {
+                return;
+            }
+
+            sender.connection = None;
+        }
+
+        if sender
+            .retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at)
+        {
+            return;
+        }
+
+        let sent = Connection::connect(&self.syslog.transport, self.syslog.timeout).and_then(
+            |mut connection| {
+                connection.send(message, self.syslog.format)?;
+
+                Ok(connection)
+            },
+        );
+
+        match sent {
+            Ok(connection) => {
+                sender.connection = Some(connection);
+                sender.backoff = MIN_BACKOFF;
+                sender.retry_at = None;
+            }
+            Err(_) => {
+                sender.retry_at = Some(Instant::now() + sender.backoff);
+                sender.backoff = (sender.backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// The syslog severity of the level.
+pub(crate) fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// A RFC 5424 header field, which is printable ASCII without spaces, or `-` when empty.
+fn header_field(value: &str, max_len: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+
+    if field.is_empty() {
+        return "-".to_string();
+    }
+
+    field
+}
+
+impl Log for SyslogLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.config.is_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.send(&self.format(record));
+    }
+
+    fn flush(&self) {}
+}
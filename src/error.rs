@@ -8,6 +8,11 @@ pub enum FtailError {
     NoDriversError,
     IoError(std::io::Error),
     PermissionsError(String),
+    UrlError(String),
+    #[cfg(feature = "regex")]
+    RegexError(regex::Error),
+    #[cfg(feature = "sqlite")]
+    SqliteError(rusqlite::Error),
 }
 
 impl std::error::Error for FtailError {}
@@ -21,6 +26,17 @@ impl Display for FtailError {
             FtailError::PermissionsError(path) => {
                 write!(f, "The path {} is read-only", path)
             }
+            FtailError::UrlError(url) => {
+                write!(
+                    f,
+                    "The URL {} is invalid, only http:// URLs are supported",
+                    url
+                )
+            }
+            #[cfg(feature = "regex")]
+            FtailError::RegexError(e) => write!(f, "Invalid regular expression: {}", e),
+            #[cfg(feature = "sqlite")]
+            FtailError::SqliteError(e) => write!(f, "SQLite error: {}", e),
         }
     }
 }
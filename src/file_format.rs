@@ -0,0 +1,9 @@
+/// The format of the lines of the file and stream drivers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileFormat {
+    /// The plain text format of the `console` driver.
+    #[default]
+    Text,
+    /// A JSON object per line with the fields of the Elastic Common Schema (ECS).
+    Ecs,
+}
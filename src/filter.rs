@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use log::{Level, Metadata, Record};
+
+pub(crate) type Predicate = Arc<dyn Fn(&Metadata) -> bool + Send + Sync>;
+pub(crate) type RecordPredicate = Arc<dyn Fn(&Record) -> bool + Send + Sync>;
+
+/// A filter that decides whether a driver logs a message.
+#[derive(Clone)]
+pub(crate) enum Filter {
+    /// Only log messages with one of the levels.
+    Levels(Vec<Level>),
+    /// Only log messages with one of the targets.
+    Targets(TargetMatcher),
+    /// Don't log messages with one of the targets.
+    ExcludedTargets(TargetMatcher),
+    /// Only log messages for which the predicate returns `true`.
+    Predicate(Predicate),
+    /// Only log records for which the predicate returns `true`.
+    RecordPredicate(RecordPredicate),
+    /// Only log messages matching one of the regular expressions.
+    #[cfg(feature = "regex")]
+    Messages(regex::RegexSet),
+    /// Don't log messages matching one of the regular expressions.
+    #[cfg(feature = "regex")]
+    ExcludedMessages(regex::RegexSet),
+}
+
+impl Filter {
+    /// Whether the metadata matches the filter. Filters on the record always match.
+    pub(crate) fn matches(&self, metadata: &Metadata) -> bool {
+        match self {
+            Filter::Levels(levels) => levels.contains(&metadata.level()),
+            Filter::Targets(targets) => targets.matches(metadata.target()),
+            Filter::ExcludedTargets(targets) => !targets.matches(metadata.target()),
+            Filter::Predicate(predicate) => predicate(metadata),
+            _ => true,
+        }
+    }
+
+    /// Whether the record matches the filter. Filters on the metadata always match, since
+    /// `matches` already checked them, so each predicate runs once per record.
+    pub(crate) fn matches_record(&self, record: &Record) -> bool {
+        match self {
+            Filter::RecordPredicate(predicate) => predicate(record),
+            #[cfg(feature = "regex")]
+            Filter::Messages(regex) => regex.is_match(&message(record)),
+            #[cfg(feature = "regex")]
+            Filter::ExcludedMessages(regex) => !regex.is_match(&message(record)),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TargetPattern {
+    /// Matches the target and its submodules, e.g. `foo` matches `foo` and `foo::bar`.
+    Module(String),
+    /// Matches the target with `*` for any characters and `?` for a single character.
+    Glob(String),
+}
+
+/// A list of target patterns, compiled once so matching a target doesn't allocate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TargetMatcher {
+    patterns: Vec<TargetPattern>,
+}
+
+impl TargetMatcher {
+    pub(crate) fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref().to_string();
+
+                if pattern.contains(['*', '?']) {
+                    TargetPattern::Glob(pattern)
+                } else {
+                    TargetPattern::Module(pattern)
+                }
+            })
+            .collect();
+
+        TargetMatcher { patterns }
+    }
+
+    pub(crate) fn matches(&self, target: &str) -> bool {
+        self.patterns.iter().any(|pattern| match pattern {
+            TargetPattern::Module(module) => target
+                .strip_prefix(module.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::")),
+            TargetPattern::Glob(glob) => glob_matches(glob.as_bytes(), target.as_bytes()),
+        })
+    }
+}
+
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(feature = "regex")]
+fn message<'a>(record: &'a Record<'a>) -> std::borrow::Cow<'a, str> {
+    match record.args().as_str() {
+        Some(message) => std::borrow::Cow::Borrowed(message),
+        None => std::borrow::Cow::Owned(record.args().to_string()),
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Write;
+
 use crate::{formatters::Config, writer::LogWriter};
 
 use super::Formatter;
@@ -14,15 +16,31 @@ impl DefaultFormatter<'_> {
 }
 
 impl<'a> Formatter for DefaultFormatter<'a> {
-    fn format(&self) -> String {
+    fn write(&self, w: &mut dyn Write) -> std::fmt::Result {
         let writer = LogWriter::new(self.record, self.config);
 
-        format!(
-            "{} {} {} {}",
-            writer.get_datetime(),
-            writer.get_level(),
-            writer.get_target(),
-            writer.get_args(),
-        )
+        write!(w, "{}", writer.get_datetime())?;
+
+        if self.config.show_elapsed {
+            write!(w, " +{:.3}s", writer.get_elapsed().as_secs_f64())?;
+        }
+
+        if self.config.show_uptime {
+            write!(w, " [{:.6}]", writer.get_uptime().as_secs_f64())?;
+        }
+
+        if writer.has_context() {
+            write!(w, " {}", writer.get_context())?;
+        }
+
+        write!(w, " {} {}", writer.get_level(), writer.get_target())?;
+
+        if self.config.show_location {
+            if let Some(location) = writer.get_location() {
+                write!(w, " {}", location)?;
+            }
+        }
+
+        write!(w, " {}", writer.get_args())
     }
 }
@@ -0,0 +1,103 @@
+use std::fmt::Write;
+
+use log::kv::{Key, Value, VisitSource};
+
+use crate::{formatters::Config, json, writer::LogWriter};
+
+use super::Formatter;
+
+/// The version of the Elastic Common Schema of the fields.
+const ECS_VERSION: &str = "1.6.0";
+
+/// Formats records as JSON objects with Elastic Common Schema (ECS) fields.
+pub struct EcsFormatter<'a> {
+    record: &'a log::Record<'a>,
+    config: &'a Config,
+}
+
+impl EcsFormatter<'_> {
+    pub fn new<'a>(record: &'a log::Record<'a>, config: &'a Config) -> EcsFormatter<'a> {
+        EcsFormatter { record, config }
+    }
+}
+
+impl<'a> Formatter for EcsFormatter<'a> {
+    fn write(&self, w: &mut dyn Write) -> std::fmt::Result {
+        let writer = LogWriter::new(self.record, self.config);
+
+        // Elasticsearch `date` fields keep milliseconds
+        write!(
+            w,
+            "{{\"@timestamp\":\"{}\",\"log.level\":\"{}\",\"message\":",
+            writer.get_utc_datetime().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            writer.get_level().as_str().to_lowercase()
+        )?;
+        json::write_string(w, writer.get_args())?;
+        write!(w, ",\"ecs.version\":\"{}\",\"log.logger\":", ECS_VERSION)?;
+        json::write_string(w, writer.get_target())?;
+
+        if let Some(file) = writer.get_file() {
+            w.write_str(",\"log.origin.file.name\":")?;
+            json::write_string(w, file)?;
+        }
+
+        if let Some(line) = writer.get_line() {
+            write!(w, ",\"log.origin.file.line\":{}", line)?;
+        }
+
+        write!(w, ",\"process.pid\":{}", writer.get_pid())?;
+
+        if let Some(thread_name) = writer.get_thread_name() {
+            w.write_str(",\"process.thread.name\":")?;
+            json::write_string(w, thread_name)?;
+        }
+
+        w.write_str(",\"host.hostname\":")?;
+        json::write_string(w, writer.get_hostname())?;
+
+        let mut labels = Labels {
+            w,
+            count: 0,
+            result: Ok(()),
+        };
+        let _ = self.record.key_values().visit(&mut labels);
+        labels.result?;
+
+        if labels.count > 0 {
+            w.write_char('}')?;
+        }
+
+        w.write_char('}')
+    }
+}
+
+/// Writes the key-values into a `labels` object, so they can't clash with the ECS fields.
+struct Labels<'w> {
+    w: &'w mut dyn Write,
+    count: usize,
+    result: std::fmt::Result,
+}
+
+impl<'kvs> VisitSource<'kvs> for Labels<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        if self.result.is_err() {
+            return Ok(());
+        }
+
+        let separator = if self.count == 0 {
+            ",\"labels\":{"
+        } else {
+            ","
+        };
+        self.count += 1;
+
+        self.result = self
+            .w
+            .write_str(separator)
+            .and_then(|_| json::write_string(self.w, key.as_str()))
+            .and_then(|_| self.w.write_char(':'))
+            .and_then(|_| json::write_value(self.w, &value));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+
+use log::kv::{Key, Value, VisitSource};
+
+use crate::{drivers::syslog::severity, json, timestamp::Timestamp};
+
+use super::Formatter;
+
+/// Formats records as GELF 1.1 messages.
+pub struct GelfFormatter<'a> {
+    record: &'a log::Record<'a>,
+    host: &'a str,
+}
+
+impl GelfFormatter<'_> {
+    pub fn new<'a>(record: &'a log::Record<'a>, host: &'a str) -> GelfFormatter<'a> {
+        GelfFormatter { record, host }
+    }
+}
+
+impl<'a> Formatter for GelfFormatter<'a> {
+    fn write(&self, w: &mut dyn Write) -> std::fmt::Result {
+        let datetime = Timestamp::current().datetime;
+
+        w.write_str("{\"version\":\"1.1\",\"host\":")?;
+        json::write_string(w, self.host)?;
+        w.write_str(",\"short_message\":")?;
+        json::write_string(w, self.record.args())?;
+        write!(
+            w,
+            ",\"timestamp\":{}.{:06},\"level\":{}",
+            datetime.timestamp(),
+            datetime.timestamp_subsec_micros(),
+            severity(self.record.level())
+        )?;
+        w.write_str(",\"_target\":")?;
+        json::write_string(w, self.record.target())?;
+
+        if let Some(file) = self.record.file() {
+            w.write_str(",\"_file\":")?;
+            json::write_string(w, file)?;
+        }
+
+        if let Some(line) = self.record.line() {
+            write!(w, ",\"_line\":{}", line)?;
+        }
+
+        let mut fields = Fields { w, result: Ok(()) };
+        let _ = self.record.key_values().visit(&mut fields);
+        fields.result?;
+
+        w.write_char('}')
+    }
+}
+
+/// Writes the key-values as additional fields.
+struct Fields<'w> {
+    w: &'w mut dyn Write,
+    result: std::fmt::Result,
+}
+
+impl Fields<'_> {
+    fn write(&mut self, key: &str, value: &Value) -> std::fmt::Result {
+        self.w.write_str(",\"_")?;
+
+        // additional field names may only contain word characters, dots and dashes
+        for c in key.chars() {
+            let c = match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | '-' => c,
+                _ => '_',
+            };
+
+            self.w.write_char(c)?;
+        }
+
+        self.w.write_str("\":")?;
+        json::write_value(self.w, value)
+    }
+}
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        // `_id` is reserved by GELF
+        if self.result.is_err() || key.as_str() == "id" {
+            return Ok(());
+        }
+
+        self.result = self.write(key.as_str(), &value);
+
+        Ok(())
+    }
+}
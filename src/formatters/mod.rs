@@ -1,12 +1,25 @@
-use log::LevelFilter;
+use std::fmt::Write;
 
-use crate::Config;
+use log::{Level, LevelFilter};
+
+use crate::{file_format::FileFormat, location::LocationFormat, Config};
 
 pub mod default;
+pub mod ecs;
+pub mod gelf;
+pub mod otlp;
 pub mod readable;
 
 pub trait Formatter {
-    fn format(&self) -> String;
+    /// Write the formatted message, without a trailing newline.
+    fn write(&self, w: &mut dyn Write) -> std::fmt::Result;
+
+    fn format(&self) -> String {
+        let mut result = String::new();
+        self.write(&mut result).unwrap();
+
+        result
+    }
 }
 
 impl Config {
@@ -14,13 +27,43 @@ impl Config {
         Config {
             level_filter: LevelFilter::Off,
             datetime_format: "%Y-%m-%d %H:%M:%S".to_string(),
+            timestamp_format: None,
+            utc_offset: None,
             #[cfg(feature = "timezone")]
             timezone: chrono_tz::Tz::UTC,
+            #[cfg(feature = "timezone")]
+            explicit_timezone: false,
             max_file_size: None,
+            multi_process: false,
+            show_elapsed: false,
+            show_uptime: false,
+            show_thread_name: false,
+            show_thread_id: false,
+            show_pid: false,
+            show_hostname: false,
+            show_location: false,
+            location_format: LocationFormat::File,
+            strip_location_prefix: None,
+            normalize_separators: false,
+            file_format: FileFormat::Text,
             levels: None,
             targets: None,
+            excluded_targets: None,
+            #[cfg(feature = "regex")]
+            messages: None,
+            #[cfg(feature = "regex")]
+            excluded_messages: None,
+            rate_limit: None,
+            suppress_duplicates: false,
+            sampling: None,
         }
     }
+
+    /// Whether a driver with this config logs messages of the level. A level filter of `Off`
+    /// logs every level.
+    pub fn is_enabled(&self, level: Level) -> bool {
+        self.level_filter == LevelFilter::Off || level <= self.level_filter
+    }
 }
 
 impl Default for Config {
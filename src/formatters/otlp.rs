@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use log::Level;
+
+use crate::{
+    drivers::otlp::Otlp,
+    json,
+    record::{FieldValue, OwnedRecord},
+};
+
+/// Write the records as an OTLP/JSON `ExportLogsServiceRequest`, with a scope per target.
+pub(crate) fn write_request(
+    w: &mut dyn Write,
+    otlp: &Otlp,
+    records: &[OwnedRecord],
+) -> std::fmt::Result {
+    w.write_str("{\"resourceLogs\":[{\"resource\":{\"attributes\":[")?;
+
+    for (i, (key, value)) in otlp.resource_attributes().enumerate() {
+        if i > 0 {
+            w.write_char(',')?;
+        }
+
+        write_attribute(w, key, &FieldValue::String(value.to_string()))?;
+    }
+
+    w.write_str("]},\"scopeLogs\":[")?;
+
+    let mut scopes: Vec<(&str, Vec<&OwnedRecord>)> = Vec::new();
+
+    for record in records {
+        match scopes
+            .iter_mut()
+            .find(|(target, _)| *target == record.target)
+        {
+            Some((_, scope)) => scope.push(record),
+            None => scopes.push((&record.target, vec![record])),
+        }
+    }
+
+    for (i, (target, records)) in scopes.iter().enumerate() {
+        if i > 0 {
+            w.write_char(',')?;
+        }
+
+        w.write_str("{\"scope\":{\"name\":")?;
+        json::write_string(w, target)?;
+        w.write_str("},\"logRecords\":[")?;
+
+        for (j, record) in records.iter().enumerate() {
+            if j > 0 {
+                w.write_char(',')?;
+            }
+
+            write_log_record(w, record)?;
+        }
+
+        w.write_str("]}")?;
+    }
+
+    w.write_str("]}]}")
+}
+
+fn write_log_record(w: &mut dyn Write, record: &OwnedRecord) -> std::fmt::Result {
+    let time = record.datetime.timestamp() as i128 * 1_000_000_000
+        + record.datetime.timestamp_subsec_nanos() as i128;
+
+    write!(
+        w,
+        "{{\"timeUnixNano\":\"{}\",\"observedTimeUnixNano\":\"{}\",\"severityNumber\":{},\"severityText\":\"{}\",\"body\":{{\"stringValue\":",
+        time,
+        time,
+        severity_number(record.level),
+        record.level
+    )?;
+    json::write_string(w, &record.message)?;
+    w.write_str("},\"attributes\":[")?;
+
+    let mut attributes: Vec<(&str, FieldValue)> = Vec::new();
+
+    if let Some(file) = &record.file {
+        attributes.push(("code.filepath", FieldValue::String(file.clone())));
+    }
+
+    if let Some(line) = record.line {
+        attributes.push(("code.lineno", FieldValue::I64(line as i64)));
+    }
+
+    if let Some(module_path) = &record.module_path {
+        attributes.push(("code.namespace", FieldValue::String(module_path.clone())));
+    }
+
+    for (key, value) in &record.key_values {
+        attributes.push((key, value.clone()));
+    }
+
+    for (i, (key, value)) in attributes.iter().enumerate() {
+        if i > 0 {
+            w.write_char(',')?;
+        }
+
+        write_attribute(w, key, value)?;
+    }
+
+    w.write_str("]}")
+}
+
+fn write_attribute(w: &mut dyn Write, key: &str, value: &FieldValue) -> std::fmt::Result {
+    w.write_str("{\"key\":")?;
+    json::write_string(w, key)?;
+    w.write_str(",\"value\":{")?;
+
+    // 64-bit integers are strings in OTLP/JSON, and values above `i64::MAX` don't fit `intValue`
+    match value {
+        FieldValue::Bool(value) => write!(w, "\"boolValue\":{}", value)?,
+        FieldValue::I64(value) => write!(w, "\"intValue\":\"{}\"", value)?,
+        FieldValue::U64(value) => write!(w, "\"stringValue\":\"{}\"", value)?,
+        FieldValue::F64(value) => write!(w, "\"doubleValue\":{}", value)?,
+        FieldValue::String(value) => {
+            w.write_str("\"stringValue\":")?;
+            json::write_string(w, value)?;
+        }
+    }
+
+    w.write_str("}}")
+}
+
+/// The OpenTelemetry severity number of the level.
+fn severity_number(level: Level) -> u8 {
+    match level {
+        Level::Trace => 1,
+        Level::Debug => 5,
+        Level::Info => 9,
+        Level::Warn => 13,
+        Level::Error => 17,
+    }
+}
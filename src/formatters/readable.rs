@@ -1,3 +1,5 @@
+use std::fmt::Write;
+
 use crate::{ansi_escape::TextStyling, writer::LogWriter, Config};
 
 use super::Formatter;
@@ -14,11 +16,9 @@ impl ReadableFormatter<'_> {
 }
 
 impl<'a> Formatter for ReadableFormatter<'a> {
-    fn format(&self) -> String {
+    fn write(&self, w: &mut dyn Write) -> std::fmt::Result {
         let writer = LogWriter::new(self.record, self.config);
 
-        let mut result = String::new();
-
         let level = match self.record.level() {
             log::Level::Trace => writer.get_level().bold().black(),
             log::Level::Debug => writer.get_level().bold().blue(),
@@ -27,21 +27,32 @@ impl<'a> Formatter for ReadableFormatter<'a> {
             log::Level::Error => writer.get_level().bold().red(),
         };
 
-        result.push_str(&format!("{} · {}\n", writer.get_datetime().black(), level));
-        result.push_str(&format!("{}\n", writer.get_args().bold()));
+        write!(w, "{}", writer.get_datetime().black())?;
+
+        if self.config.show_elapsed {
+            let elapsed = format!("+{:.3}s", writer.get_elapsed().as_secs_f64());
+            write!(w, " {}", elapsed.black())?;
+        }
+
+        if self.config.show_uptime {
+            let uptime = format!("[{:.6}]", writer.get_uptime().as_secs_f64());
+            write!(w, " {}", uptime.black())?;
+        }
+
+        write!(w, " · {}", level)?;
+
+        if writer.has_context() {
+            write!(w, " {}", writer.get_context().black())?;
+        }
 
-        let file = writer.get_file();
-        let line = writer.get_line();
+        writeln!(w)?;
+        writeln!(w, "{}", writer.get_args().bold())?;
 
-        if file.is_some() && line.is_some() {
-            result.push_str(&format!(
-                "{}{}{}\n",
-                file.unwrap().black(),
-                ":".black(),
-                line.unwrap().black()
-            ));
+        // one styled span, so terminals can detect the location as a link
+        if let Some(location) = writer.get_location() {
+            writeln!(w, "{}", location.black())?;
         }
 
-        result
+        Ok(())
     }
 }
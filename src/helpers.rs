@@ -1,18 +1,181 @@
-use std::{fs::File, io::LineWriter, path::PathBuf, sync::Mutex};
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::LineWriter,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::Config;
+use crate::{
+    file_format::FileFormat,
+    formatters::{default::DefaultFormatter, ecs::EcsFormatter, Formatter},
+    Config,
+};
+
+/// Buffers above this capacity are not kept for reuse.
+const MAX_REUSED_BUFFER_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    static LINE_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Format the message with a trailing newline into a buffer that is reused by the current
+/// thread, and pass it to `f`.
+pub(crate) fn format_line<R>(formatter: &dyn Formatter, f: impl FnOnce(&str) -> R) -> R {
+    LINE_BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+        Ok(mut buffer) => {
+            buffer.clear();
+            formatter.write(&mut *buffer).unwrap();
+            buffer.push('\n');
+
+            let result = f(&buffer);
+
+            if buffer.capacity() > MAX_REUSED_BUFFER_CAPACITY {
+                *buffer = String::new();
+            }
+
+            result
+        }
+        // the buffer is in use when formatting a message logs another message
+        Err(_) => {
+            let mut line = formatter.format();
+            line.push('\n');
+
+            f(&line)
+        }
+    })
+}
+
+/// Format the message in the file format of the config with `format_line`.
+pub(crate) fn format_file_line<R>(
+    record: &log::Record,
+    config: &Config,
+    f: impl FnOnce(&str) -> R,
+) -> R {
+    match config.file_format {
+        FileFormat::Text => format_line(&DefaultFormatter::new(record, config), f),
+        FileFormat::Ecs => format_line(&EcsFormatter::new(record, config), f),
+    }
+}
+
+/// Connect a UDP socket to the first resolved address, bound to the unspecified address of the
+/// same family so IPv6 collectors work too.
+pub(crate) fn connect_udp(address: &str) -> std::io::Result<UdpSocket> {
+    let mut last_error = None;
+
+    for address in address.to_socket_addrs()? {
+        let local: SocketAddr = match address {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+
+        match UdpSocket::bind(local).and_then(|socket| socket.connect(address).map(|_| socket)) {
+            Ok(socket) => return Ok(socket),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| std::io::ErrorKind::NotFound.into()))
+}
+
+/// Connect a TCP stream to the first reachable resolved address. Connecting and writing time out,
+/// so a stalled peer can't block the logging threads.
+pub(crate) fn connect_tcp(address: &str, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => {
+                stream.set_write_timeout(Some(timeout))?;
+
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| std::io::ErrorKind::NotFound.into()))
+}
+
+/// An advisory lock (`flock`) on a lock file next to the log file.
+///
+/// The lock is taken on a separate file because rotation renames the log file itself, and a
+/// lock on the old inode would not stop another process from writing to the new one.
+pub(crate) struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    pub(crate) fn acquire(path: &Path) -> std::io::Result<FileLock> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+
+        file.lock()?;
+
+        Ok(FileLock { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+pub(crate) fn lock_file_path(path: &Path) -> PathBuf {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+
+    PathBuf::from(lock_path)
+}
+
+pub(crate) fn open_append(path: &Path) -> std::io::Result<File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+}
+
+/// Reopen the log file if another process has rotated it away from `file_path`.
+pub(crate) fn reopen_if_moved(file: &mut LineWriter<File>, file_path: &Path) {
+    if !is_moved(file.get_ref(), file_path) {
+        return;
+    }
+
+    *file = LineWriter::new(open_append(file_path).unwrap());
+}
+
+#[cfg(unix)]
+fn is_moved(file: &File, file_path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let current = file.metadata().unwrap();
+
+    match std::fs::metadata(file_path) {
+        Ok(md) => md.dev() != current.dev() || md.ino() != current.ino(),
+        Err(_) => true,
+    }
+}
+
+// the file can't be compared without inode numbers, so it's never reopened
+#[cfg(not(unix))]
+fn is_moved(_file: &File, _file_path: &Path) -> bool {
+    false
+}
 
 pub(crate) fn rotate_if_exceeds_max_file_size(
-    file: &Mutex<LineWriter<File>>,
-    file_path: PathBuf,
+    file: &mut LineWriter<File>,
+    file_path: &Path,
     config: &Config,
 ) {
     if config.max_file_size.is_none() {
         return;
     }
 
-    let mut file = file.lock().unwrap();
-
     let md = file.get_ref().metadata().unwrap();
 
     if md.len() > config.max_file_size.unwrap() {
@@ -28,7 +191,7 @@ pub(crate) fn rotate_if_exceeds_max_file_size(
 
         std::fs::rename(path, &new_path).unwrap();
 
-        let new_file = std::fs::File::create(path).unwrap();
+        let new_file = open_append(file_path).unwrap();
         *file = LineWriter::new(new_file);
     }
 }
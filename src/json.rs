@@ -0,0 +1,64 @@
+use std::fmt::{Display, Write};
+
+use log::kv::Value;
+
+/// Write the value as a JSON string, including the quotes.
+pub(crate) fn write_string(w: &mut dyn Write, value: impl Display) -> std::fmt::Result {
+    w.write_char('"')?;
+    write!(Escape(w), "{}", value)?;
+    w.write_char('"')
+}
+
+/// Write a key-value value as a JSON number or boolean, or otherwise as a string.
+pub(crate) fn write_value(w: &mut dyn Write, value: &Value) -> std::fmt::Result {
+    if let Some(value) = value.to_bool() {
+        return write!(w, "{}", value);
+    }
+
+    if let Some(value) = value.to_i64() {
+        return write!(w, "{}", value);
+    }
+
+    if let Some(value) = value.to_u64() {
+        return write!(w, "{}", value);
+    }
+
+    if let Some(value) = value.to_f64().filter(|value| value.is_finite()) {
+        return write!(w, "{}", value);
+    }
+
+    write_string(w, value)
+}
+
+/// Escapes the characters that aren't allowed in a JSON string.
+struct Escape<'w>(&'w mut dyn Write);
+
+impl Write for Escape<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let mut start = 0;
+
+        for (i, c) in s.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                c if c.is_control() => {
+                    self.0.write_str(&s[start..i])?;
+                    write!(self.0, "\\u{:04x}", c as u32)?;
+                    start = i + c.len_utf8();
+
+                    continue;
+                }
+                _ => continue,
+            };
+
+            self.0.write_str(&s[start..i])?;
+            self.0.write_str(escaped)?;
+            start = i + c.len_utf8();
+        }
+
+        self.0.write_str(&s[start..])
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, Weak,
+};
+
+use log::{Level, LevelFilter};
+
+/// A handle to change the level of a driver at runtime.
+///
+/// Like the level passed to a driver, `LevelFilter::Off` logs all levels.
+#[derive(Clone)]
+pub struct LevelHandle {
+    inner: Arc<LevelHandleInner>,
+}
+
+struct LevelHandleInner {
+    level: AtomicUsize,
+    max_levels: Mutex<Vec<Weak<MaxLevel>>>,
+}
+
+impl LevelHandle {
+    /// Create a new handle with the given level.
+    pub fn new(level: LevelFilter) -> Self {
+        LevelHandle {
+            inner: Arc::new(LevelHandleInner {
+                level: AtomicUsize::new(level as usize),
+                max_levels: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// The current level.
+    pub fn get(&self) -> LevelFilter {
+        level_filter_from_usize(self.inner.level.load(Ordering::Relaxed))
+    }
+
+    /// Change the level and update the maximum level of the logger.
+    pub fn set(&self, level: LevelFilter) {
+        self.inner.level.store(level as usize, Ordering::Relaxed);
+
+        let mut max_levels = self.inner.max_levels.lock().unwrap();
+        max_levels.retain(|max_level| max_level.strong_count() > 0);
+
+        for max_level in max_levels.iter().filter_map(Weak::upgrade) {
+            max_level.update();
+        }
+    }
+
+    pub(crate) fn is_enabled(&self, level: Level) -> bool {
+        let level_filter = self.get();
+
+        level_filter == LevelFilter::Off || level <= level_filter
+    }
+
+    fn register(&self, max_level: &Arc<MaxLevel>) {
+        self.inner
+            .max_levels
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(max_level));
+    }
+}
+
+/// The most verbose level a driver can log.
+#[derive(Clone)]
+pub(crate) enum DriverLevel {
+    Static(LevelFilter),
+    Dynamic(LevelHandle),
+}
+
+impl DriverLevel {
+    fn max_level(&self) -> LevelFilter {
+        let level = match self {
+            DriverLevel::Static(level) => *level,
+            DriverLevel::Dynamic(handle) => handle.get(),
+        };
+
+        // drivers log all levels when the level is `Off`
+        if level == LevelFilter::Off {
+            return LevelFilter::Trace;
+        }
+
+        level
+    }
+}
+
+/// The most verbose level any driver logs, so records above it can be skipped cheaply.
+pub(crate) struct MaxLevel {
+    drivers: Vec<DriverLevel>,
+    levels: Option<Vec<Level>>,
+    value: AtomicUsize,
+    is_global: AtomicBool,
+}
+
+impl MaxLevel {
+    pub(crate) fn new(drivers: Vec<DriverLevel>, levels: Option<Vec<Level>>) -> Arc<Self> {
+        let max_level = Arc::new(MaxLevel {
+            drivers,
+            levels,
+            value: AtomicUsize::new(LevelFilter::Trace as usize),
+            is_global: AtomicBool::new(false),
+        });
+
+        for driver in &max_level.drivers {
+            if let DriverLevel::Dynamic(handle) = driver {
+                handle.register(&max_level);
+            }
+        }
+
+        max_level.update();
+
+        max_level
+    }
+
+    pub(crate) fn get(&self) -> LevelFilter {
+        level_filter_from_usize(self.value.load(Ordering::Relaxed))
+    }
+
+    /// Keep `log::max_level()` in sync with this maximum level.
+    pub(crate) fn set_global(&self) {
+        self.is_global.store(true, Ordering::Relaxed);
+
+        log::set_max_level(self.get());
+    }
+
+    fn update(&self) {
+        let mut max_level = self
+            .drivers
+            .iter()
+            .map(DriverLevel::max_level)
+            .max()
+            .unwrap_or(LevelFilter::Off);
+
+        if let Some(levels) = &self.levels {
+            let max_filtered_level = levels
+                .iter()
+                .max()
+                .map_or(LevelFilter::Off, |level| level.to_level_filter());
+
+            max_level = max_level.min(max_filtered_level);
+        }
+
+        self.value.store(max_level as usize, Ordering::Relaxed);
+
+        if self.is_global.load(Ordering::Relaxed) {
+            log::set_max_level(max_level);
+        }
+    }
+}
+
+fn level_filter_from_usize(level: usize) -> LevelFilter {
+    LevelFilter::iter().nth(level).unwrap_or(LevelFilter::Trace)
+}
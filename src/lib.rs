@@ -6,6 +6,17 @@
 //! - [Formatted console](#formatted-console)
 //! - [Single file](#single-file)
 //! - [Daily file](#daily-file)
+//! - [Elastic Common Schema](#elastic-common-schema)
+//! - [Memory](#memory)
+//! - [Syslog](#syslog)
+//! - [Journald](#journald)
+//! - [Stream](#stream)
+//! - [GELF](#gelf)
+//! - [HTTP](#http)
+//! - [OpenTelemetry](#opentelemetry)
+//! - [SQLite](#sqlite)
+//! - [Error context](#error-context)
+//! - [Channel and callback](#channel-and-callback)
 //! - [Custom driver](#custom-driver)
 //!
 //! ## Usage
@@ -39,10 +50,65 @@
 //! You can set the following configuration options:
 //!
 //! - `.datetime_format("%Y-%m-%d %H:%M:%S.3f")` to set the datetime format
+//! - `.timestamp_format(TimestampFormat::Rfc3339)` to log RFC 3339 (`Rfc3339`, `Rfc3339Utc`) or Unix epoch (`UnixSeconds`, `UnixMillis`, `UnixNanos`) timestamps instead of the datetime format; the last call of `.datetime_format(..)` and `.timestamp_format(..)` wins
 //! - `.timezone(ftail::Tz::UTC)` to set the timezone [requires feature `timezone`]
+//! - `.utc()` or `.fixed_offset("+02:00".parse().unwrap())` to log in UTC or with a fixed offset, without the `timezone` feature. Daily log files are named after the date in the same timezone, or the local date when neither these nor `.timezone(..)` are set
 //! - `.max_file_size(100)` to set the maximum file size in MB (will move older logs to .old{N})
+//! - `.multi_process(true)` to lock the log files so multiple processes can write to the same file
+//! - `.show_elapsed(true)` to show the time since the logger was initialized, e.g. `+12.345s`
+//! - `.show_uptime(true)` to show the monotonic time since the logger was initialized, e.g. `[12.345678]`
+//! - `.show_hostname(true)`, `.show_pid(true)`, `.show_thread_name(true)` and `.show_thread_id(true)` to show where the message was logged, e.g. `web-1 pid:1234 worker#3`
+//! - `.show_location(true)` to show the source location, e.g. `src/db.rs:42`, in the plain drivers (the `formatted_console` driver always shows it)
+//! - `.location_format(LocationFormat::ModulePath)` to show the module path, e.g. `my_app::db`, instead of the file and line
+//! - `.strip_location_prefix("crates/")` to strip a prefix from the file of the source location
+//! - `.normalize_separators(true)` to use `/` as the path separator in the file of the source location
+//! - `.file_format(FileFormat::Ecs)` to write JSON lines with Elastic Common Schema fields in the `single_file`, `daily_file` and `stream` drivers
 //! - `.filter_levels(vec![Level::Debug, Level::Error])` only log messages with the specified levels
 //! - `.filter_targets(vec!["foo", "bar"])` only log messages with the specified targets
+//! - `.exclude_targets(vec!["foo::db", "hyper*"])` don't log messages with the specified targets
+//! - `.filter(|metadata| metadata.target() != "health")` only log messages for which the closure returns `true`
+//! - `.filter_record(|record| record.args().to_string() != "ping")` same as `.filter(..)`, but the closure receives the whole record
+//! - `.filter_messages(vec!["^GET /api"])` only log messages matching one of the regular expressions in the message itself, not the formatted line [requires feature `regex`]
+//! - `.exclude_messages(vec!["^GET /health"])` don't log messages matching one of the regular expressions in the message itself, not the formatted line [requires feature `regex`]
+//! - `.rate_limit(RateLimit::per_callsite(10, 1.0))` to limit the number of messages per callsite or target
+//! - `.suppress_duplicates(true)` to collapse consecutive identical messages into "last message repeated N times", which is logged before the next different message, every 30 seconds while the duplicates go on, and on `log::logger().flush()`
+//! - `.sampling(Sampling::new().level(Level::Debug, 0.01))` to only keep a fraction of the messages per level or target, where a target also matches its submodules
+//!
+//! Targets match their submodules as well, so `foo` matches `foo` and `foo::db`. Use `*` and `?` for glob patterns, e.g. `foo::*::db`.
+//!
+//! The rate limit, duplicate suppression and sampling can also be applied to a single driver with `.with_rate_limit(..)`, `.with_suppress_duplicates()` and `.with_sampling(..)`.
+//!
+//! Use a `LevelHandle` to change the level of the most recently added driver at runtime:
+//!
+//! ```rust
+//! use ftail::level::LevelHandle;
+//!
+//! let console_level = LevelHandle::new(LevelFilter::Info);
+//!
+//! Ftail::new()
+//!     .console(LevelFilter::Info)
+//!     .with_level_handle(&console_level)
+//!     .init()?;
+//!
+//! console_level.set(LevelFilter::Debug);
+//! ```
+//!
+//! The following filters apply to the most recently added driver only:
+//!
+//! - `.with_levels(vec![Level::Debug, Level::Error])` only log messages with the specified levels
+//! - `.with_targets(vec!["audit"])` only log messages with the specified targets
+//! - `.with_excluded_targets(vec!["audit"])` don't log messages with the specified targets
+//! - `.with_filter(|metadata| metadata.target() != "health")` only log messages for which the closure returns `true`
+//! - `.with_record_filter(|record| record.args().to_string() != "ping")` same as `.with_filter(..)`, but the closure receives the whole record
+//!
+//! ```rust
+//! Ftail::new()
+//!     .daily_file("logs/audit", LevelFilter::Info)
+//!     .with_targets(vec!["audit"])
+//!     .console(LevelFilter::Debug)
+//!     .with_excluded_targets(vec!["audit"])
+//!     .init()?;
+//! ```
 //!
 //! ## Drivers
 //!
@@ -135,9 +201,283 @@
 //!     .init()?;
 //! ```
 //!
+//! ### Elastic Common Schema
+//!
+//! The `single_file`, `daily_file` and `stream` drivers write the lines in the format set with `.file_format(...)`, or with `.with_file_format(...)` for the most recently added driver. With `FileFormat::Ecs`, each line is a JSON object with the Elastic Common Schema fields `@timestamp`, `log.level`, `message`, `ecs.version`, `log.logger`, `log.origin.file.name`, `log.origin.file.line`, `process.pid`, `process.thread.name` and `host.hostname`, with the key-values in a `labels` object, e.g. for Filebeat or Elastic Agent.
+//!
+//! ```rust
+//! use ftail::file_format::FileFormat;
+//!
+//! Ftail::new()
+//!     .single_file("logs/app.log", true, LevelFilter::Info)
+//!     .daily_file("logs", LevelFilter::Info)
+//!     .with_file_format(FileFormat::Ecs)
+//!     .init()?;
+//! ```
+//!
+//! ```json
+//! {"@timestamp":"2024-09-17T17:37:22.402Z","log.level":"info","message":"Listening","ecs.version":"1.6.0","log.logger":"app","log.origin.file.name":"src/main.rs","log.origin.file.line":12,"process.pid":1234,"process.thread.name":"main","host.hostname":"web-1","labels":{"port":8080}}
+//! ```
+//!
+//! ### Memory
+//!
+//! Keeps the last log messages in memory. The messages can be read through the `MemoryBuffer` handle.
+//!
+//! The `memory` driver takes the following parameters:
+//!
+//! - `buffer`: the buffer handle, created with a `Capacity::Records(n)` or `Capacity::Bytes(n)` capacity
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! use ftail::drivers::memory::{Capacity, MemoryBuffer};
+//!
+//! let buffer = MemoryBuffer::new(Capacity::Records(100));
+//!
+//! Ftail::new()
+//!     .memory(&buffer, LevelFilter::Info)
+//!     .init()?;
+//!
+//! let records = buffer.snapshot(); // or `buffer.drain()` to also remove them
+//! ```
+//!
+//! ### Syslog
+//!
+//! Sends the messages to a syslog daemon, framed per RFC 5424 or RFC 3164. The log levels are mapped to the syslog severities `err`, `warning`, `info` and `debug`. Messages that can't be sent are dropped: connecting and sending time out after 5 seconds, or the duration set with `.timeout(..)`, and after a failed reconnect the messages are dropped for a backoff that doubles up to 30 seconds.
+//!
+//! The `syslog` driver takes the following parameters:
+//!
+//! - `syslog`: the configuration, created with `Syslog::local()` for `/dev/log`, `Syslog::unix(path)`, `Syslog::udp(address)` or `Syslog::tcp(address)`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! use ftail::drivers::syslog::{Facility, Syslog, SyslogFormat};
+//!
+//! Ftail::new()
+//!     .syslog(
+//!         Syslog::udp("127.0.0.1:514")
+//!             .format(SyslogFormat::Rfc3164)
+//!             .facility(Facility::Local0)
+//!             .app_name("my-app"),
+//!         LevelFilter::Info,
+//!     )
+//!     .init()?;
+//! ```
+//!
+//! ### Journald
+//!
+//! Sends the messages to the native socket of systemd-journald as structured fields: `MESSAGE`, `PRIORITY`, `SYSLOG_IDENTIFIER`, `TARGET`, `CODE_FILE`, `CODE_LINE`, `CODE_MODULE` and the key-values of the record, e.g. `user_id` as `USER_ID`, except those with the name of one of these fields. Entries too large for a datagram are passed in a sealed memory file [requires feature `journald`, Linux only].
+//!
+//! The `journald` driver takes the following parameters:
+//!
+//! - `journald`: the configuration, created with `Journald::new()`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! use ftail::drivers::journald::Journald;
+//!
+//! Ftail::new()
+//!     .journald(Journald::new().syslog_identifier("my-app"), LevelFilter::Info)
+//!     .init()?;
+//!
+//! log::info!(user_id = 42; "user logged in");
+//! ```
+//!
+//! ### Stream
+//!
+//! Streams the messages to a TCP or Unix stream socket, e.g. of a local log collector, one message per line in the format set with `.file_format(...)` or `.with_file_format(...)`, e.g. ECS JSON lines. The messages are sent from a background thread, so logging doesn't block when the peer is slow or unavailable. The driver reconnects with exponential backoff and keeps the messages in a bounded buffer in the meantime.
+//!
+//! The `stream` driver takes the following parameters:
+//!
+//! - `stream`: the configuration, created with `Stream::tcp(address)` or `Stream::unix(path)`
+//! - `level`: the minumum log level to log
+//!
+//! The configuration has the following options:
+//!
+//! - `.capacity(10_000)` the number of messages to keep while the peer is unavailable, the oldest messages are dropped when the buffer is full
+//! - `.spill_to_disk("logs/spill.log")` append the messages that don't fit in the buffer, or are still buffered when the logger is flushed or dropped while the peer is unavailable, to a file instead, and send them once the peer is available again
+//! - `.backoff(Duration::from_millis(100), Duration::from_secs(30))` the first and maximum delay between reconnect attempts
+//!
+//! ```rust
+//! use ftail::drivers::stream::Stream;
+//!
+//! Ftail::new()
+//!     .stream(
+//!         Stream::tcp("127.0.0.1:5170").spill_to_disk("logs/spill.log"),
+//!         LevelFilter::Info,
+//!     )
+//!     .init()?;
+//! ```
+//!
+//! ### GELF
+//!
+//! Sends the messages as GELF 1.1 to a UDP input, e.g. of Graylog. The level is mapped to the syslog severity, and the target, file, line and key-values of the record are sent as the additional fields `_target`, `_file`, `_line` and `_{key}`. Messages larger than the chunk size are split into chunks.
+//!
+//! The `gelf` driver takes the following parameters:
+//!
+//! - `gelf`: the configuration, created with `Gelf::udp(address)`
+//! - `level`: the minumum log level to log
+//!
+//! The configuration has the following options:
+//!
+//! - `.host("web-1")` the `host` field, the hostname of the machine by default
+//! - `.chunk_size(8154)` the maximum size of a datagram, 1420 bytes by default
+//! - `.compress(true)` compress the messages with gzip [requires feature `gzip`]
+//!
+//! ```rust
+//! use ftail::drivers::gelf::Gelf;
+//!
+//! Ftail::new()
+//!     .gelf(Gelf::udp("127.0.0.1:12201"), LevelFilter::Info)
+//!     .init()?;
+//!
+//! log::info!(user_id = 42; "user logged in");
+//! ```
+//!
+//! ### HTTP
+//!
+//! Sends batches of messages to an HTTP ingestion endpoint, e.g. of Loki or Elasticsearch, from a background thread. Only `http://` URLs are supported, use a local agent or proxy for TLS.
+//!
+//! The `http` driver takes the following parameters:
+//!
+//! - `http`: the configuration, created with `Http::new(url)`
+//! - `level`: the minumum log level to log
+//!
+//! The configuration has the following options:
+//!
+//! - `.body(HttpBody::Loki)` the body format: `HttpBody::Ndjson` (default), `HttpBody::Loki`, `HttpBody::ElasticsearchBulk(index)` or `HttpBody::Otlp(otlp)`
+//! - `.header("Authorization", "Bearer token")` add a header to the requests
+//! - `.batch_size(100)`, `.batch_bytes(1024 * 1024)` and `.batch_interval(Duration::from_secs(1))` send a batch once it has this many messages or bytes, or once its oldest message has waited this long
+//! - `.capacity(10_000)` the number of messages to keep while the endpoint is unavailable, the oldest messages are dropped when the buffer is full and written to the dead-letter file, or otherwise counted in a warning sent with the next batch
+//! - `.retries(3)` and `.backoff(Duration::from_millis(500), Duration::from_secs(30))` retry failed batches with exponential backoff, when the endpoint can't be reached or responds with status 429 or 5xx
+//! - `.dead_letter("logs/dead_letter.log")` append the bodies of the batches that couldn't be delivered, and of the messages dropped from a full buffer, to a file
+//!
+//! ```rust
+//! use ftail::drivers::http::{Http, HttpBody};
+//!
+//! Ftail::new()
+//!     .http(
+//!         Http::new("http://127.0.0.1:3100/loki/api/v1/push")
+//!             .body(HttpBody::Loki)
+//!             .dead_letter("logs/dead_letter.log"),
+//!         LevelFilter::Info,
+//!     )
+//!     .init()?;
+//! ```
+//!
+//! ### OpenTelemetry
+//!
+//! Writes the messages in the OpenTelemetry logs data model as OTLP/JSON, one export request per line, e.g. for the `otlpjsonfile` receiver of the OpenTelemetry Collector. Each record has the severity number and text, the timestamp, the target as the instrumentation scope, and the file, line, module path and key-values as attributes. The resource has the `service.name` and `host.name` attributes.
+//!
+//! The `otlp_file` driver takes the following parameters:
+//!
+//! - `path`: the path to the log file
+//! - `otlp`: the resource, created with `Otlp::new(service_name)`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! use ftail::drivers::otlp::Otlp;
+//!
+//! Ftail::new()
+//!     .otlp_file(
+//!         "logs/otlp.jsonl",
+//!         Otlp::new("my-app").attribute("service.version", "1.2.0"),
+//!         LevelFilter::Info,
+//!     )
+//!     .init()?;
+//! ```
+//!
+//! To send the messages to an OTLP/HTTP receiver, use the `http` driver with the `HttpBody::Otlp` body:
+//!
+//! ```rust
+//! Ftail::new()
+//!     .http(
+//!         Http::new("http://127.0.0.1:4318/v1/logs").body(HttpBody::Otlp(Otlp::new("my-app"))),
+//!         LevelFilter::Info,
+//!     )
+//!     .init()?;
+//! ```
+//!
+//! ### SQLite
+//!
+//! Inserts the messages into the `logs` table of a SQLite database in WAL mode, so they can be queried with SQL, e.g. on desktop or edge devices [requires feature `sqlite`]. The table has the columns `id`, `timestamp` (RFC 3339 in UTC), `level`, `target`, `message`, `file`, `line` and `key_values` (a JSON object). The messages are inserted from a background thread in batches, one transaction per batch.
+//!
+//! The `sqlite` driver takes the following parameters:
+//!
+//! - `sqlite`: the configuration, created with `Sqlite::new(path)`
+//! - `level`: the minumum log level to log
+//!
+//! The configuration has the following options:
+//!
+//! - `.batch_size(100)` the number of messages per transaction
+//! - `.batch_interval(Duration::from_secs(1))` how long a message may wait for the batch to fill up
+//! - `.capacity(10_000)` the number of messages kept in memory, the oldest are dropped when full
+//! - `.max_age(Duration::from_secs(7 * 24 * 60 * 60))` delete messages older than the maximum age
+//! - `.max_rows(100_000)` delete the oldest messages when the table has more rows
+//!
+//! The retention policy is applied when the driver starts and then every minute.
+//!
+//! ```rust
+//! use ftail::drivers::sqlite::Sqlite;
+//!
+//! Ftail::new()
+//!     .sqlite(
+//!         Sqlite::new("logs/app.db").max_age(Duration::from_secs(7 * 24 * 60 * 60)),
+//!         LevelFilter::Info,
+//!     )
+//!     .init()?;
+//! ```
+//!
+//! ```sql
+//! SELECT timestamp, message FROM logs
+//! WHERE level = 'ERROR' AND json_extract(key_values, '$.user_id') = 42
+//! ORDER BY id DESC LIMIT 20;
+//! ```
+//!
+//! ### Error context
+//!
+//! Wraps the most recently added driver. The last debug and trace messages of each thread are kept in memory and only logged when an error is logged on the same thread.
+//!
+//! The `with_error_context` modifier takes the following parameters:
+//!
+//! - `capacity`: the number of debug and trace messages to keep per thread
+//!
+//! ```rust
+//! Ftail::new()
+//!     .daily_file("logs", LevelFilter::Info)
+//!     .with_error_context(50)
+//!     .init()?;
+//! ```
+//!
+//! ### Channel and callback
+//!
+//! Sends an owned copy of each message, an `OwnedRecord` with the `level`, `target`, `message`, `datetime`, `module_path`, `file`, `line` and `key_values`, to a `std::sync::mpsc` channel or a closure, e.g. to show the messages in a TUI or collect them in a test harness.
+//!
+//! The `channel` driver takes the following parameters:
+//!
+//! - `sender`: the sending half of the channel
+//! - `level`: the minumum log level to log
+//!
+//! The `callback` driver takes the following parameters:
+//!
+//! - `callback`: the closure that is called with each message
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! let (sender, receiver) = std::sync::mpsc::channel();
+//!
+//! Ftail::new()
+//!     .channel(sender, LevelFilter::Info)
+//!     .callback(|record| eprintln!("{}: {}", record.target, record.message), LevelFilter::Warn)
+//!     .init()?;
+//!
+//! for record in receiver {
+//!     println!("{} {}", record.level, record.message);
+//! }
+//! ```
+//!
 //! ### Custom driver
 //!
-//! Create your own log driver.
+//! Create your own log driver. `Config::is_enabled` checks the level filter of the driver. The `ftail::context` module has the hostname, process id and thread id shown by `show_hostname`, `show_pid` and `show_thread_id`.
 //!
 //! ```rust
 //! Ftail::new()
@@ -155,11 +495,7 @@
 //!
 //! impl Log for CustomLogger {
 //!     fn enabled(&self, metadata: &log::Metadata) -> bool {
-//!         if self.config.level_filter == LevelFilter::Off {
-//!             return true;
-//!         }
-//!
-//!         metadata.level() <= self.config.level_filter
+//!         self.config.is_enabled(metadata.level())
 //!     }
 //!
 //!     fn log(&self, record: &log::Record) {
@@ -184,42 +520,133 @@
 //! 19:37:22.403 [WARN] This is a warning message
 //! 19:37:22.403 [ERROR] This is an error message
 //! ```
+//!
+//! ## Testing
+//!
+//! Enable the `testing` feature to assert on log output in tests. `testing::capture()` captures the records logged on the current thread, so tests running in parallel don't interfere with each other.
+//!
+//! ```rust
+//! use log::Level;
+//!
+//! let logs = ftail::testing::capture();
+//!
+//! log::warn!(target: "db", "slow query took 5s");
+//!
+//! logs.assert_logged(Level::Warn, "db", r"slow query took \d+s");
+//! logs.assert_not_logged(Level::Error, "db", ".*");
+//! ```
+//!
+//! Use `testing::install` to install your own configuration on the current thread, e.g. with the `capture` driver:
+//!
+//! ```rust
+//! use ftail::drivers::capture::Captured;
+//!
+//! let captured = Captured::new();
+//!
+//! let _guard = ftail::testing::install(
+//!     Ftail::new()
+//!         .capture(&captured, LevelFilter::Info)
+//!         .filter_targets(vec!["db"]),
+//! )?;
+//! ```
+//!
 
 use drivers::{
-    console::ConsoleLogger, daily_file::DailyFileLogger, formatted_console::FormattedConsoleLogger,
+    callback::CallbackLogger,
+    console::ConsoleLogger,
+    daily_file::DailyFileLogger,
+    error_context::ErrorContextLogger,
+    filter::FilterLogger,
+    formatted_console::FormattedConsoleLogger,
+    gelf::{Gelf, GelfLogger},
+    http::{Http, HttpLogger},
+    level_handle::LevelHandleLogger,
+    memory::{MemoryBuffer, MemoryLogger},
+    otlp::{Otlp, OtlpFileLogger},
+    rate_limit::RateLimitLogger,
+    sampling::SamplingLogger,
     single_file::SingleFileLogger,
+    stream::{Stream, StreamLogger},
+    syslog::{Syslog, SyslogLogger},
 };
 use error::FtailError;
+use file_format::FileFormat;
+use filter::{Filter, TargetMatcher};
+use level::{DriverLevel, LevelHandle, MaxLevel};
+use location::LocationFormat;
 use log::{Level, LevelFilter, Log};
+use rate_limit::{RateLimit, RateLimiter};
+use record::OwnedRecord;
+use sampling::{Sampler, Sampling};
+use std::sync::{mpsc::Sender, Arc};
+use timestamp::{Timestamp, TimestampFormat};
+
+#[cfg(feature = "testing")]
+use drivers::capture::{CaptureLogger, Captured};
+#[cfg(all(target_os = "linux", feature = "journald"))]
+use drivers::journald::{Journald, JournaldLogger};
+#[cfg(feature = "sqlite")]
+use drivers::sqlite::{Sqlite, SqliteLogger};
 
+pub use chrono::FixedOffset;
 #[cfg(feature = "timezone")]
 pub use chrono_tz::Tz;
 
 /// Module containing the ANSI escape codes.
 pub mod ansi_escape;
+/// Module containing the hostname, process id and thread id of the records.
+pub mod context;
 /// Module containing the drivers.
 pub mod drivers;
 /// Module containing the error type.
 pub mod error;
+/// Module containing the file formats.
+pub mod file_format;
+mod filter;
 mod formatters;
 mod helpers;
+mod json;
+/// Module containing the level handle.
+pub mod level;
+/// Module containing the source location formats.
+pub mod location;
+/// Module containing the rate limit configuration.
+pub mod rate_limit;
+/// Module containing the owned record of the `channel` and `callback` drivers.
+pub mod record;
+/// Module containing the sampling configuration.
+pub mod sampling;
+/// Module containing the test helpers.
+#[cfg(feature = "testing")]
+pub mod testing;
 #[cfg(test)]
 mod tests;
+/// Module containing the timestamp formats.
+pub mod timestamp;
 mod writer;
 
 /// The main struct for configuring the logger.
 pub struct Ftail {
     drivers: Vec<LogDriver>,
     initialized_drivers: Vec<InitializedLogDriver>,
+    rate_limiter: Option<RateLimiter>,
+    sampler: Option<Sampler>,
+    targets: Option<TargetMatcher>,
+    excluded_targets: Option<TargetMatcher>,
+    filters: Vec<Filter>,
+    max_level: Arc<MaxLevel>,
     config: Config,
 }
 
 unsafe impl Send for Ftail {}
 unsafe impl Sync for Ftail {}
 
+type DriverConstructor = Box<dyn Fn(Config) -> Result<Box<dyn Log + Send + Sync>, FtailError>>;
+
 pub(crate) struct LogDriver {
-    constructor: Box<dyn Fn(Config) -> Box<dyn Log + Send + Sync>>,
+    constructor: DriverConstructor,
     level: log::LevelFilter,
+    max_level: DriverLevel,
 }
 
 pub(crate) struct InitializedLogDriver {
@@ -231,11 +658,37 @@ pub(crate) struct InitializedLogDriver {
 pub struct Config {
     pub level_filter: LevelFilter,
     pub datetime_format: String,
+    pub timestamp_format: Option<TimestampFormat>,
+    pub utc_offset: Option<FixedOffset>,
     #[cfg(feature = "timezone")]
     pub timezone: chrono_tz::Tz,
+    /// Whether the timezone was set with [`Ftail::timezone`]. Daily log files are named after
+    /// the local date otherwise.
+    #[cfg(feature = "timezone")]
+    pub explicit_timezone: bool,
     pub max_file_size: Option<u64>,
+    pub multi_process: bool,
+    pub show_elapsed: bool,
+    pub show_uptime: bool,
+    pub show_thread_name: bool,
+    pub show_thread_id: bool,
+    pub show_pid: bool,
+    pub show_hostname: bool,
+    pub show_location: bool,
+    pub location_format: LocationFormat,
+    pub strip_location_prefix: Option<String>,
+    pub normalize_separators: bool,
+    pub file_format: FileFormat,
     pub levels: Option<Vec<Level>>,
     pub targets: Option<Vec<String>>,
+    pub excluded_targets: Option<Vec<String>>,
+    #[cfg(feature = "regex")]
+    pub messages: Option<Vec<String>>,
+    #[cfg(feature = "regex")]
+    pub excluded_messages: Option<Vec<String>>,
+    pub rate_limit: Option<RateLimit>,
+    pub suppress_duplicates: bool,
+    pub sampling: Option<Sampling>,
 }
 
 impl Ftail {
@@ -244,6 +697,12 @@ impl Ftail {
         Self {
             drivers: Vec::new(),
             initialized_drivers: Vec::new(),
+            rate_limiter: None,
+            sampler: None,
+            targets: None,
+            excluded_targets: None,
+            filters: Vec::new(),
+            max_level: MaxLevel::new(Vec::new(), None),
             config: Config::new(),
         }
     }
@@ -252,13 +711,38 @@ impl Ftail {
     /// Set the timezone for the logger.
     pub fn timezone(mut self, timezone: chrono_tz::Tz) -> Self {
         self.config.timezone = timezone;
+        self.config.explicit_timezone = true;
+        self.config.utc_offset = None;
 
         self
     }
 
-    /// Set the datetime format for the logger.
+    /// Log the datetimes in UTC.
+    pub fn utc(self) -> Self {
+        self.fixed_offset(FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// Log the datetimes with a fixed offset from UTC, e.g. `"+02:00".parse().unwrap()`,
+    /// instead of the timezone.
+    pub fn fixed_offset(mut self, utc_offset: FixedOffset) -> Self {
+        self.config.utc_offset = Some(utc_offset);
+
+        self
+    }
+
+    /// Set the datetime format for the logger. Clears the format set with
+    /// [`Ftail::timestamp_format`], so the last of the two calls wins.
     pub fn datetime_format(mut self, datetime_format: &str) -> Self {
         self.config.datetime_format = datetime_format.to_string();
+        self.config.timestamp_format = None;
+
+        self
+    }
+
+    /// Use a timestamp format instead of the strftime `datetime_format`. A later call to
+    /// [`Ftail::datetime_format`] clears it.
+    pub fn timestamp_format(mut self, timestamp_format: TimestampFormat) -> Self {
+        self.config.timestamp_format = Some(timestamp_format);
 
         self
     }
@@ -270,6 +754,93 @@ impl Ftail {
         self
     }
 
+    /// Show the time since the logger was initialized.
+    pub fn show_elapsed(mut self, show_elapsed: bool) -> Self {
+        self.config.show_elapsed = show_elapsed;
+
+        self
+    }
+
+    /// Show the monotonic time since the logger was initialized, which isn't affected by changes
+    /// to the system clock.
+    pub fn show_uptime(mut self, show_uptime: bool) -> Self {
+        self.config.show_uptime = show_uptime;
+
+        self
+    }
+
+    /// Show the name of the thread that logged the message.
+    pub fn show_thread_name(mut self, show_thread_name: bool) -> Self {
+        self.config.show_thread_name = show_thread_name;
+
+        self
+    }
+
+    /// Show the id of the thread that logged the message.
+    pub fn show_thread_id(mut self, show_thread_id: bool) -> Self {
+        self.config.show_thread_id = show_thread_id;
+
+        self
+    }
+
+    /// Show the process id.
+    pub fn show_pid(mut self, show_pid: bool) -> Self {
+        self.config.show_pid = show_pid;
+
+        self
+    }
+
+    /// Show the hostname.
+    pub fn show_hostname(mut self, show_hostname: bool) -> Self {
+        self.config.show_hostname = show_hostname;
+
+        self
+    }
+
+    /// Show the source location of the message in the `console`, `single_file`, `daily_file`
+    /// and `memory` drivers. The `formatted_console` driver always shows it.
+    pub fn show_location(mut self, show_location: bool) -> Self {
+        self.config.show_location = show_location;
+
+        self
+    }
+
+    /// Set whether the source location shows the file and line or the module path.
+    pub fn location_format(mut self, location_format: LocationFormat) -> Self {
+        self.config.location_format = location_format;
+
+        self
+    }
+
+    /// Set the format of the lines of the `single_file`, `daily_file` and `stream` drivers.
+    pub fn file_format(mut self, file_format: FileFormat) -> Self {
+        self.config.file_format = file_format;
+
+        self
+    }
+
+    /// Strip a prefix, e.g. the workspace directory, from the file of the source location.
+    pub fn strip_location_prefix(mut self, prefix: &str) -> Self {
+        self.config.strip_location_prefix = Some(prefix.to_string());
+
+        self
+    }
+
+    /// Use `/` as the path separator in the file of the source location.
+    pub fn normalize_separators(mut self, normalize_separators: bool) -> Self {
+        self.config.normalize_separators = normalize_separators;
+
+        self
+    }
+
+    /// Lock the log files with an advisory file lock around writes and rotation, so multiple
+    /// processes can safely log to the same file. Files are always opened in append mode.
+    pub fn multi_process(mut self, multi_process: bool) -> Self {
+        self.config.multi_process = multi_process;
+
+        self
+    }
+
     /// Only log messages with the specified levels. The default is to log all levels.
     pub fn filter_levels(mut self, levels: Vec<Level>) -> Self {
         self.config.levels = Some(levels);
@@ -284,9 +855,78 @@ impl Ftail {
         self
     }
 
+    /// Don't log messages with the specified targets.
+    pub fn exclude_targets(mut self, targets: Vec<&str>) -> Self {
+        self.config.excluded_targets = Some(targets.iter().map(|s| s.to_string()).collect());
+
+        self
+    }
+
+    /// Only log messages for which the closure returns `true`.
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&log::Metadata) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Filter::Predicate(Arc::new(filter)));
+
+        self
+    }
+
+    /// Only log records for which the closure returns `true`. Unlike [`Ftail::filter`], the
+    /// closure can inspect the message.
+    pub fn filter_record<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&log::Record) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Filter::RecordPredicate(Arc::new(filter)));
+
+        self
+    }
+
+    #[cfg(feature = "regex")]
+    /// Only log messages matching one of the regular expressions. The expressions match the
+    /// message itself, without the datetime, level, target or other fields of the formatted line.
+    pub fn filter_messages(mut self, patterns: Vec<&str>) -> Self {
+        self.config.messages = Some(patterns.iter().map(|s| s.to_string()).collect());
+
+        self
+    }
+
+    #[cfg(feature = "regex")]
+    /// Don't log messages matching one of the regular expressions. The expressions match the
+    /// message itself, without the datetime, level, target or other fields of the formatted line.
+    pub fn exclude_messages(mut self, patterns: Vec<&str>) -> Self {
+        self.config.excluded_messages = Some(patterns.iter().map(|s| s.to_string()).collect());
+
+        self
+    }
+
+    /// Limit the number of messages per callsite or target. The default is no limit.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.config.rate_limit = Some(rate_limit);
+
+        self
+    }
+
+    /// Collapse consecutive identical messages into a single "last message repeated N times"
+    /// message.
+    pub fn suppress_duplicates(mut self, suppress_duplicates: bool) -> Self {
+        self.config.suppress_duplicates = suppress_duplicates;
+
+        self
+    }
+
+    /// Only keep a fraction of the messages per level or target. The default is to keep all
+    /// messages.
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.config.sampling = Some(sampling);
+
+        self
+    }
+
     fn add_driver<F>(mut self, constructor: F, level: log::LevelFilter) -> Self
     where
-        F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
+        F: Fn(Config) -> Result<Box<dyn Log + Send + Sync>, FtailError> + 'static,
     {
         self.drivers.push(LogDriver::new(constructor, level));
         self
@@ -295,7 +935,7 @@ impl Ftail {
     /// Add a driver that logs messages to the console.
     pub fn console(self, level: log::LevelFilter) -> Self {
         let constructor =
-            |config: Config| Box::new(ConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>;
+            |config: Config| Ok(Box::new(ConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>);
 
         self.add_driver(constructor, level)
     }
@@ -303,7 +943,7 @@ impl Ftail {
     /// Add a driver that logs formatted messages to the console.
     pub fn formatted_console(self, level: log::LevelFilter) -> Self {
         let constructor = |config: Config| {
-            Box::new(FormattedConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>
+            Ok(Box::new(FormattedConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>)
         };
 
         self.add_driver(constructor, level)
@@ -314,8 +954,8 @@ impl Ftail {
         let path = path.to_string();
 
         let constructor = move |config: Config| {
-            Box::new(SingleFileLogger::new(&path, append, config).unwrap())
-                as Box<dyn Log + Send + Sync>
+            Ok(Box::new(SingleFileLogger::new(&path, append, config)?)
+                as Box<dyn Log + Send + Sync>)
         };
 
         self.add_driver(constructor, level)
@@ -326,7 +966,130 @@ impl Ftail {
         let path = path.to_string();
 
         let constructor = move |config: Config| {
-            Box::new(DailyFileLogger::new(&path, config).unwrap()) as Box<dyn Log + Send + Sync>
+            Ok(Box::new(DailyFileLogger::new(&path, config)?) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that writes messages as OpenTelemetry OTLP/JSON lines to a file.
+    pub fn otlp_file(self, path: &str, otlp: Otlp, level: log::LevelFilter) -> Self {
+        let path = path.to_string();
+
+        let constructor = move |config: Config| {
+            Ok(Box::new(OtlpFileLogger::new(&path, otlp.clone(), config)?)
+                as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that keeps the last log messages in memory.
+    pub fn memory(self, buffer: &MemoryBuffer, level: log::LevelFilter) -> Self {
+        let buffer = buffer.clone();
+
+        let constructor = move |config: Config| {
+            Ok(Box::new(MemoryLogger::new(buffer.clone(), config)) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that sends messages to a syslog daemon.
+    pub fn syslog(self, syslog: Syslog, level: log::LevelFilter) -> Self {
+        let constructor = move |config: Config| {
+            Ok(Box::new(SyslogLogger::new(syslog.clone(), config)?) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that sends GELF messages to a UDP input.
+    pub fn gelf(self, gelf: Gelf, level: log::LevelFilter) -> Self {
+        let constructor = move |config: Config| {
+            Ok(Box::new(GelfLogger::new(gelf.clone(), config)?) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that sends batches of messages to an HTTP endpoint.
+    pub fn http(self, http: Http, level: log::LevelFilter) -> Self {
+        let constructor = move |config: Config| {
+            Ok(Box::new(HttpLogger::new(http.clone(), config)?) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that streams messages to a TCP or Unix stream socket.
+    pub fn stream(self, stream: Stream, level: log::LevelFilter) -> Self {
+        let constructor = move |config: Config| {
+            Ok(Box::new(StreamLogger::new(stream.clone(), config)?) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "journald"))]
+    /// Add a driver that sends messages to journald.
+    pub fn journald(self, journald: Journald, level: log::LevelFilter) -> Self {
+        let constructor = move |config: Config| {
+            Ok(Box::new(JournaldLogger::new(journald.clone(), config)?)
+                as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    #[cfg(feature = "sqlite")]
+    /// Add a driver that inserts messages into a SQLite database.
+    pub fn sqlite(self, sqlite: Sqlite, level: log::LevelFilter) -> Self {
+        let constructor = move |config: Config| {
+            Ok(Box::new(SqliteLogger::new(sqlite.clone(), config)?) as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    #[cfg(feature = "testing")]
+    /// Add a driver that captures log records so tests can assert on them.
+    pub fn capture(self, captured: &Captured, level: log::LevelFilter) -> Self {
+        let captured = captured.clone();
+
+        let constructor = move |config: Config| {
+            Ok(Box::new(CaptureLogger::new(captured.clone(), config))
+                as Box<dyn Log + Send + Sync>)
+        };
+
+        self.add_driver(constructor, level)
+    }
+
+    /// Add a driver that sends owned copies of the messages to a channel.
+    pub fn channel(self, sender: Sender<OwnedRecord>, level: log::LevelFilter) -> Self {
+        // messages logged after the receiver is dropped are discarded
+        self.callback(
+            move |record| {
+                let _ = sender.send(record);
+            },
+            level,
+        )
+    }
+
+    /// Add a driver that passes owned copies of the messages to a closure.
+    pub fn callback<F>(self, callback: F, level: log::LevelFilter) -> Self
+    where
+        F: Fn(OwnedRecord) + Send + Sync + 'static,
+    {
+        let callback = Arc::new(callback);
+
+        let constructor = move |config: Config| {
+            let callback = callback.clone();
+
+            Ok(
+                Box::new(CallbackLogger::new(move |record| callback(record), config))
+                    as Box<dyn Log + Send + Sync>,
+            )
         };
 
         self.add_driver(constructor, level)
@@ -337,17 +1100,191 @@ impl Ftail {
     where
         F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
     {
-        self.add_driver(constructor, level)
+        self.add_driver(move |config| Ok(constructor(config)), level)
+    }
+
+    /// Keep the last debug and trace messages of each thread in memory and log them when an error
+    /// is logged. Applies to the most recently added driver.
+    pub fn with_error_context(self, capacity: usize) -> Self {
+        self.wrap_last_driver("with_error_context", move |constructor, config| {
+            let mut driver_config = config.clone();
+            driver_config.level_filter = LevelFilter::Trace;
+
+            Ok(Box::new(ErrorContextLogger::new(
+                constructor(driver_config)?,
+                capacity,
+                config,
+            )))
+        })
+        .with_max_level(DriverLevel::Static(LevelFilter::Trace))
+    }
+
+    /// Control the level with the handle, so it can be changed at runtime. Applies to the most
+    /// recently added driver.
+    pub fn with_level_handle(self, handle: &LevelHandle) -> Self {
+        let handle = handle.clone();
+
+        self.wrap_last_driver("with_level_handle", {
+            let handle = handle.clone();
+
+            move |constructor, mut config| {
+                config.level_filter = LevelFilter::Trace;
+
+                Ok(Box::new(LevelHandleLogger::new(
+                    constructor(config)?,
+                    handle.clone(),
+                )))
+            }
+        })
+        .with_max_level(DriverLevel::Dynamic(handle))
+    }
+
+    /// Limit the number of messages per callsite or target. Applies to the most recently added
+    /// driver.
+    pub fn with_rate_limit(self, rate_limit: RateLimit) -> Self {
+        self.wrap_last_driver("with_rate_limit", move |constructor, config| {
+            Ok(Box::new(RateLimitLogger::new(
+                constructor(config)?,
+                RateLimiter::new(Some(rate_limit), false),
+            )))
+        })
+    }
+
+    /// Collapse consecutive identical messages into a single "last message repeated N times"
+    /// message. Applies to the most recently added driver.
+    pub fn with_suppress_duplicates(self) -> Self {
+        self.wrap_last_driver("with_suppress_duplicates", |constructor, config| {
+            Ok(Box::new(RateLimitLogger::new(
+                constructor(config)?,
+                RateLimiter::new(None, true),
+            )))
+        })
+    }
+
+    /// Only keep a fraction of the messages per level or target. Applies to the most recently
+    /// added driver.
+    pub fn with_sampling(self, sampling: Sampling) -> Self {
+        self.wrap_last_driver("with_sampling", move |constructor, config| {
+            Ok(Box::new(SamplingLogger::new(
+                constructor(config)?,
+                Sampler::new(sampling.clone()),
+            )))
+        })
+    }
+
+    /// Set the format of the lines of the most recently added `single_file`, `daily_file` or
+    /// `stream` driver, instead of the format set with [`Ftail::file_format`].
+    pub fn with_file_format(self, file_format: FileFormat) -> Self {
+        self.wrap_last_driver("with_file_format", move |constructor, mut config| {
+            config.file_format = file_format;
+
+            constructor(config)
+        })
+    }
+
+    /// Only log messages with the specified levels. Applies to the most recently added driver.
+    pub fn with_levels(self, levels: Vec<Level>) -> Self {
+        self.with_driver_filter("with_levels", Filter::Levels(levels))
+    }
+
+    /// Only log messages with the specified targets. Applies to the most recently added driver.
+    pub fn with_targets(self, targets: Vec<&str>) -> Self {
+        let targets = TargetMatcher::new(&targets);
+
+        self.with_driver_filter("with_targets", Filter::Targets(targets))
+    }
+
+    /// Don't log messages with the specified targets. Applies to the most recently added driver.
+    pub fn with_excluded_targets(self, targets: Vec<&str>) -> Self {
+        let targets = TargetMatcher::new(&targets);
+
+        self.with_driver_filter("with_excluded_targets", Filter::ExcludedTargets(targets))
+    }
+
+    /// Only log messages for which the closure returns `true`. Applies to the most recently added
+    /// driver.
+    pub fn with_filter<F>(self, filter: F) -> Self
+    where
+        F: Fn(&log::Metadata) -> bool + Send + Sync + 'static,
+    {
+        self.with_driver_filter("with_filter", Filter::Predicate(Arc::new(filter)))
+    }
+
+    /// Only log records for which the closure returns `true`. Applies to the most recently added
+    /// driver.
+    pub fn with_record_filter<F>(self, filter: F) -> Self
+    where
+        F: Fn(&log::Record) -> bool + Send + Sync + 'static,
+    {
+        self.with_driver_filter(
+            "with_record_filter",
+            Filter::RecordPredicate(Arc::new(filter)),
+        )
+    }
+
+    fn with_driver_filter(self, method: &str, filter: Filter) -> Self {
+        self.wrap_last_driver(method, move |constructor, config| {
+            Ok(Box::new(FilterLogger::new(
+                constructor(config)?,
+                filter.clone(),
+            )))
+        })
+    }
+
+    fn wrap_last_driver<F>(mut self, method: &str, wrapper: F) -> Self
+    where
+        F: Fn(&DriverConstructor, Config) -> Result<Box<dyn Log + Send + Sync>, FtailError>
+            + 'static,
+    {
+        let LogDriver {
+            constructor,
+            level,
+            max_level,
+        } = self
+            .drivers
+            .pop()
+            .unwrap_or_else(|| panic!("{}() must be called after adding a driver", method));
+
+        self.add_driver(move |config: Config| wrapper(&constructor, config), level)
+            .with_max_level(max_level)
+    }
+
+    fn with_max_level(mut self, max_level: DriverLevel) -> Self {
+        if let Some(driver) = self.drivers.last_mut() {
+            driver.max_level = max_level;
+        }
+
+        self
     }
 
     /// Initialize the logger.
-    pub fn init(mut self) -> Result<(), FtailError> {
+    pub fn init(self) -> Result<(), FtailError> {
+        let ftail = self.build()?;
+        let max_level = Arc::clone(&ftail.max_level);
+
+        log::set_boxed_logger(Box::new(ftail)).map_err(FtailError::SetLoggerError)?;
+        max_level.set_global();
+
+        Ok(())
+    }
+
+    pub(crate) fn build(mut self) -> Result<Self, FtailError> {
         if self.drivers.is_empty() {
             return Err(FtailError::NoDriversError);
         }
 
+        Timestamp::startup();
+
         let drivers = std::mem::take(&mut self.drivers);
 
+        self.max_level = MaxLevel::new(
+            drivers
+                .iter()
+                .map(|driver| driver.max_level.clone())
+                .collect(),
+            self.config.levels.clone(),
+        );
+
         self.initialized_drivers = drivers
             .into_iter()
             .map(|driver| {
@@ -356,33 +1293,73 @@ impl Ftail {
 
                 driver.init(config)
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
+
+        self.targets = self.config.targets.as_deref().map(TargetMatcher::new);
+        self.excluded_targets = self
+            .config
+            .excluded_targets
+            .as_deref()
+            .map(TargetMatcher::new);
+        self.sampler = self.config.sampling.clone().map(Sampler::new);
 
-        log::set_max_level(log::LevelFilter::Trace);
-        log::set_boxed_logger(Box::new(self)).map_err(FtailError::SetLoggerError)
+        #[cfg(feature = "regex")]
+        {
+            if let Some(messages) = &self.config.messages {
+                let regex = regex::RegexSet::new(messages).map_err(FtailError::RegexError)?;
+                self.filters.push(Filter::Messages(regex));
+            }
+
+            if let Some(messages) = &self.config.excluded_messages {
+                let regex = regex::RegexSet::new(messages).map_err(FtailError::RegexError)?;
+                self.filters.push(Filter::ExcludedMessages(regex));
+            }
+        }
+
+        if self.config.rate_limit.is_some() || self.config.suppress_duplicates {
+            self.rate_limiter = Some(RateLimiter::new(
+                self.config.rate_limit,
+                self.config.suppress_duplicates,
+            ));
+        }
+
+        Ok(self)
     }
 }
 
 impl LogDriver {
     fn new<F>(constructor: F, level: log::LevelFilter) -> Self
     where
-        F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
+        F: Fn(Config) -> Result<Box<dyn Log + Send + Sync>, FtailError> + 'static,
     {
         Self {
             constructor: Box::new(constructor),
             level,
+            max_level: DriverLevel::Static(level),
         }
     }
 
-    fn init(self, config: Config) -> InitializedLogDriver {
-        InitializedLogDriver {
-            driver: (self.constructor)(config),
+    fn init(self, config: Config) -> Result<InitializedLogDriver, FtailError> {
+        Ok(InitializedLogDriver {
+            driver: (self.constructor)(config)?,
+        })
+    }
+}
+
+impl Ftail {
+    fn log_to_drivers(&self, record: &log::Record) {
+        for driver in &self.initialized_drivers {
+            driver.driver.log(record);
         }
     }
 }
 
 impl Log for Ftail {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
+        if metadata.level() > self.max_level.get() {
+            return false;
+        }
+
         if self.config.levels.is_some()
             && !self
                 .config
@@ -394,31 +1371,48 @@ impl Log for Ftail {
             return false;
         }
 
-        if self.config.targets.is_some()
-            && !self
-                .config
-                .targets
-                .as_ref()
-                .unwrap()
-                .contains(&metadata.target().to_string())
-        {
-            return false;
+        if let Some(targets) = &self.targets {
+            if !targets.matches(metadata.target()) {
+                return false;
+            }
+        }
+
+        if let Some(excluded_targets) = &self.excluded_targets {
+            if excluded_targets.matches(metadata.target()) {
+                return false;
+            }
         }
 
-        true
+        self.filters.iter().all(|filter| filter.matches(metadata))
     }
 
     fn log(&self, record: &log::Record) {
-        if !self.enabled(record.metadata()) {
+        if !self.enabled(record.metadata())
+            || !self
+                .filters
+                .iter()
+                .all(|filter| filter.matches_record(record))
+        {
             return;
         }
 
-        for driver in &self.initialized_drivers {
-            driver.driver.log(record);
+        if let Some(sampler) = &self.sampler {
+            if !sampler.sample(record.metadata()) {
+                return;
+            }
         }
+
+        Timestamp::now().scope(|| match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.log(record, |record| self.log_to_drivers(record)),
+            None => self.log_to_drivers(record),
+        });
     }
 
     fn flush(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.flush(|record| self.log_to_drivers(record));
+        }
+
         for driver in &self.initialized_drivers {
             driver.driver.flush();
         }
@@ -0,0 +1,10 @@
+/// What the source location of a message shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocationFormat {
+    /// The file and line, e.g. `src/db.rs:42`. There's no column, since `log` records don't
+    /// have one.
+    #[default]
+    File,
+    /// The module path, e.g. `my_app::db`.
+    ModulePath,
+}
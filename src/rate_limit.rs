@@ -0,0 +1,257 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{Level, Record};
+
+use crate::timestamp::Timestamp;
+
+/// How often the count of a burst of duplicates that is still going on is reported.
+const REPEATED_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What the token buckets of a [`RateLimit`] are kept for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// One bucket per file and line that logs.
+    Callsite,
+    /// One bucket per target.
+    Target,
+}
+
+/// A token bucket rate limit. Each bucket holds up to `burst` messages and refills with
+/// `per_second` messages per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub key: RateLimitKey,
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    /// Limit the number of messages per callsite.
+    pub fn per_callsite(burst: u32, per_second: f64) -> Self {
+        RateLimit {
+            key: RateLimitKey::Callsite,
+            burst,
+            per_second,
+        }
+    }
+
+    /// Limit the number of messages per target.
+    pub fn per_target(burst: u32, per_second: f64) -> Self {
+        RateLimit {
+            key: RateLimitKey::Target,
+            burst,
+            per_second,
+        }
+    }
+}
+
+#[derive(Hash, PartialEq, Eq)]
+enum BucketKey {
+    Callsite(Option<String>, Option<u32>),
+    Target(String),
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+struct LastMessage {
+    level: Level,
+    target: String,
+    message: String,
+    repeated: usize,
+    /// When the duplicates were last reported, or the message was first logged.
+    reported_at: Instant,
+    /// The time of the last duplicate.
+    repeated_at: Timestamp,
+}
+
+/// Applies a rate limit and collapses consecutive identical messages.
+pub(crate) struct RateLimiter {
+    rate_limit: Option<RateLimit>,
+    suppress_duplicates: bool,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+    last_message: Mutex<Option<LastMessage>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate_limit: Option<RateLimit>, suppress_duplicates: bool) -> Self {
+        RateLimiter {
+            rate_limit,
+            suppress_duplicates,
+            buckets: Mutex::new(HashMap::new()),
+            last_message: Mutex::new(None),
+        }
+    }
+
+    /// Pass the record to `log` unless it is a duplicate or exceeds the rate limit. A
+    /// "last message repeated N times" message is logged before the first different message, and
+    /// every 30 seconds while the duplicates go on.
+    pub(crate) fn log(&self, record: &Record, log: impl Fn(&Record)) {
+        if self.suppress_duplicates && self.is_duplicate(record, &log) {
+            return;
+        }
+
+        if !self.allow(record) {
+            return;
+        }
+
+        log(record);
+    }
+
+    /// Log the "last message repeated N times" message of the pending duplicates.
+    pub(crate) fn flush(&self, log: impl Fn(&Record)) {
+        let repeated = self
+            .last_message
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(LastMessage::take_repeated);
+
+        if let Some(repeated) = repeated {
+            repeated.log(&log);
+        }
+    }
+
+    fn is_duplicate(&self, record: &Record, log: &impl Fn(&Record)) -> bool {
+        let message = record.args().to_string();
+
+        // the lock is released before logging, so a driver that logs from `log` doesn't deadlock
+        let (is_duplicate, repeated) = {
+            let mut last_message = self.last_message.lock().unwrap();
+
+            let (is_duplicate, repeated) = match last_message.as_mut() {
+                Some(last_message)
+                    if last_message.level == record.level()
+                        && last_message.target == record.target()
+                        && last_message.message == message =>
+                {
+                    last_message.repeated += 1;
+                    last_message.repeated_at = Timestamp::current();
+
+                    let elapsed = last_message
+                        .repeated_at
+                        .instant
+                        .saturating_duration_since(last_message.reported_at);
+
+                    let repeated = if elapsed >= REPEATED_INTERVAL {
+                        last_message.take_repeated()
+                    } else {
+                        None
+                    };
+
+                    (true, repeated)
+                }
+                Some(last_message) => (false, last_message.take_repeated()),
+                None => (false, None),
+            };
+
+            if !is_duplicate {
+                let timestamp = Timestamp::current();
+
+                *last_message = Some(LastMessage {
+                    level: record.level(),
+                    target: record.target().to_string(),
+                    message,
+                    repeated: 0,
+                    reported_at: timestamp.instant,
+                    repeated_at: timestamp,
+                });
+            }
+
+            (is_duplicate, repeated)
+        };
+
+        if let Some(repeated) = repeated {
+            repeated.log(log);
+        }
+
+        is_duplicate
+    }
+
+    fn allow(&self, record: &Record) -> bool {
+        let rate_limit = match self.rate_limit {
+            Some(rate_limit) => rate_limit,
+            None => return true,
+        };
+
+        let key = match rate_limit.key {
+            RateLimitKey::Callsite => {
+                BucketKey::Callsite(record.file().map(|f| f.to_string()), record.line())
+            }
+            RateLimitKey::Target => BucketKey::Target(record.target().to_string()),
+        };
+
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: rate_limit.burst as f64,
+            refilled_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens =
+            (bucket.tokens + elapsed * rate_limit.per_second).min(rate_limit.burst as f64);
+        bucket.refilled_at = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+
+        bucket.tokens -= 1.0;
+
+        true
+    }
+}
+
+impl LastMessage {
+    /// Take the pending duplicates, if any.
+    fn take_repeated(&mut self) -> Option<Repeated> {
+        if self.repeated == 0 {
+            return None;
+        }
+
+        self.reported_at = self.repeated_at.instant;
+
+        Some(Repeated {
+            level: self.level,
+            target: self.target.clone(),
+            count: std::mem::take(&mut self.repeated),
+            timestamp: self.repeated_at,
+        })
+    }
+}
+
+/// The duplicates of the last message that haven't been reported yet.
+struct Repeated {
+    level: Level,
+    target: String,
+    count: usize,
+    timestamp: Timestamp,
+}
+
+impl Repeated {
+    /// Log the message with the time of the last duplicate.
+    fn log(&self, log: &impl Fn(&Record)) {
+        self.timestamp.scope(|| {
+            let record = |args: std::fmt::Arguments| {
+                log(&Record::builder()
+                    .level(self.level)
+                    .target(&self.target)
+                    .args(args)
+                    .build())
+            };
+
+            match self.count {
+                1 => record(format_args!("last message repeated once")),
+                count => record(format_args!("last message repeated {} times", count)),
+            }
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::{Display, Write};
+
+use chrono::{DateTime, Local};
+use log::{
+    kv::{Key, Value, VisitSource},
+    Level,
+};
+
+use crate::{json, timestamp::Timestamp};
+
+/// A record that owns its data, so it can be kept after `log` returns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedRecord {
+    pub datetime: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub module_path: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub key_values: Vec<(String, FieldValue)>,
+}
+
+impl OwnedRecord {
+    pub fn new(record: &log::Record) -> OwnedRecord {
+        let mut key_values = KeyValues(Vec::new());
+        let _ = record.key_values().visit(&mut key_values);
+
+        OwnedRecord {
+            datetime: Timestamp::current().datetime,
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            module_path: record
+                .module_path()
+                .map(|module_path| module_path.to_string()),
+            file: record.file().map(|file| file.to_string()),
+            line: record.line(),
+            key_values: key_values.0,
+        }
+    }
+}
+
+/// An owned key-value value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    String(String),
+}
+
+impl FieldValue {
+    fn new(value: &Value) -> FieldValue {
+        if let Some(value) = value.to_bool() {
+            return FieldValue::Bool(value);
+        }
+
+        if let Some(value) = value.to_i64() {
+            return FieldValue::I64(value);
+        }
+
+        if let Some(value) = value.to_u64() {
+            return FieldValue::U64(value);
+        }
+
+        if let Some(value) = value.to_f64().filter(|value| value.is_finite()) {
+            return FieldValue::F64(value);
+        }
+
+        FieldValue::String(value.to_string())
+    }
+
+    /// Write the value as a JSON number or boolean, or otherwise as a string.
+    pub(crate) fn write_json(&self, w: &mut dyn Write) -> std::fmt::Result {
+        match self {
+            FieldValue::Bool(value) => write!(w, "{}", value),
+            FieldValue::I64(value) => write!(w, "{}", value),
+            FieldValue::U64(value) => write!(w, "{}", value),
+            FieldValue::F64(value) => write!(w, "{}", value),
+            FieldValue::String(value) => json::write_string(w, value),
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Bool(value) => write!(f, "{}", value),
+            FieldValue::I64(value) => write!(f, "{}", value),
+            FieldValue::U64(value) => write!(f, "{}", value),
+            FieldValue::F64(value) => write!(f, "{}", value),
+            FieldValue::String(value) => f.write_str(value),
+        }
+    }
+}
+
+struct KeyValues(Vec<(String, FieldValue)>);
+
+impl<'kvs> VisitSource<'kvs> for KeyValues {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0
+            .push((key.as_str().to_string(), FieldValue::new(&value)));
+
+        Ok(())
+    }
+}
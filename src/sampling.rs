@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::{Level, Metadata};
+
+use crate::filter::TargetMatcher;
+
+/// The fraction of messages to keep per level and per target. Levels without a rate are always
+/// kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sampling {
+    levels: Vec<(Level, f64)>,
+    targets: Vec<(TargetMatcher, Level, f64)>,
+    seed: Option<u64>,
+}
+
+impl Sampling {
+    /// Create a sampling configuration that keeps all messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the `rate` fraction (`0.0` to `1.0`) of the messages with the given level.
+    pub fn level(mut self, level: Level, rate: f64) -> Self {
+        self.levels.retain(|(l, _)| *l != level);
+        self.levels.push((level, rate));
+
+        self
+    }
+
+    /// Keep the `rate` fraction (`0.0` to `1.0`) of the messages with the given target and level.
+    /// The target matches its submodules too, or is a glob pattern with `*` and `?`, like in
+    /// `filter_targets`; the first matching target is used. Overrides the rate of the level.
+    pub fn target(mut self, target: &str, level: Level, rate: f64) -> Self {
+        let target = TargetMatcher::new(&[target]);
+
+        self.targets.retain(|(t, l, _)| *t != target || *l != level);
+        self.targets.push((target, level, rate));
+
+        self
+    }
+
+    /// Use a fixed seed so the sampled messages are the same on every run, e.g. in tests.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    fn rate(&self, metadata: &Metadata) -> f64 {
+        let target_rate = self.targets.iter().find(|(target, level, _)| {
+            *level == metadata.level() && target.matches(metadata.target())
+        });
+
+        if let Some((_, _, rate)) = target_rate {
+            return *rate;
+        }
+
+        self.levels
+            .iter()
+            .find(|(level, _)| *level == metadata.level())
+            .map_or(1.0, |(_, rate)| *rate)
+    }
+}
+
+/// Decides which messages to keep, using a SplitMix64 random number generator.
+pub(crate) struct Sampler {
+    sampling: Sampling,
+    state: AtomicU64,
+}
+
+impl Sampler {
+    pub(crate) fn new(sampling: Sampling) -> Self {
+        let seed = sampling.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64)
+        });
+
+        Sampler {
+            sampling,
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    pub(crate) fn sample(&self, metadata: &Metadata) -> bool {
+        let rate = self.sampling.rate(metadata);
+
+        if rate >= 1.0 {
+            return true;
+        }
+
+        if rate <= 0.0 {
+            return false;
+        }
+
+        // the upper 53 bits as a fraction in [0, 1)
+        let fraction = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+
+        fraction < rate
+    }
+
+    fn next(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,114 @@
+use std::{cell::RefCell, ops::Deref, sync::Arc, sync::Mutex};
+
+use log::{LevelFilter, Log};
+
+use crate::{drivers::capture::Captured, error::FtailError, Ftail};
+
+thread_local! {
+    static LOCAL_LOGGERS: RefCell<Vec<Arc<Ftail>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The global logger that forwards records to the logger installed on the current thread.
+struct LocalDispatcher;
+
+impl Log for LocalDispatcher {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        current().is_some_and(|ftail| ftail.enabled(metadata))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if let Some(ftail) = current() {
+            ftail.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(ftail) = current() {
+            ftail.flush();
+        }
+    }
+}
+
+fn current() -> Option<Arc<Ftail>> {
+    LOCAL_LOGGERS.with(|loggers| loggers.borrow().last().cloned())
+}
+
+fn install_dispatcher() -> Result<(), FtailError> {
+    static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+    let mut installed = INSTALLED.lock().unwrap();
+
+    if !*installed {
+        log::set_boxed_logger(Box::new(LocalDispatcher)).map_err(FtailError::SetLoggerError)?;
+        log::set_max_level(LevelFilter::Trace);
+
+        *installed = true;
+    }
+
+    Ok(())
+}
+
+/// Keeps a logger installed on the current thread. The logger is removed when dropped.
+pub struct LocalGuard {
+    ftail: Arc<Ftail>,
+}
+
+impl Drop for LocalGuard {
+    fn drop(&mut self) {
+        LOCAL_LOGGERS.with(|loggers| {
+            loggers
+                .borrow_mut()
+                .retain(|ftail| !Arc::ptr_eq(ftail, &self.ftail))
+        });
+    }
+}
+
+/// Install the logger for the current thread only, until the returned guard is dropped.
+///
+/// This sets a global logger that forwards each record to the logger of the thread that logged
+/// it, so tests running in parallel don't race on `log::set_boxed_logger`. Records logged from
+/// other threads, e.g. spawned by the test, are not forwarded.
+pub fn install(ftail: Ftail) -> Result<LocalGuard, FtailError> {
+    install_dispatcher()?;
+
+    let ftail = Arc::new(ftail.build()?);
+
+    LOCAL_LOGGERS.with(|loggers| loggers.borrow_mut().push(Arc::clone(&ftail)));
+
+    Ok(LocalGuard { ftail })
+}
+
+/// Captures all records logged on the current thread while in scope.
+pub struct Capture {
+    captured: Captured,
+    _guard: LocalGuard,
+}
+
+impl Deref for Capture {
+    type Target = Captured;
+
+    fn deref(&self) -> &Self::Target {
+        &self.captured
+    }
+}
+
+/// Capture all records logged on the current thread until the returned value is dropped.
+///
+/// ```rust
+/// let logs = ftail::testing::capture();
+///
+/// log::warn!(target: "db", "slow query took 5s");
+///
+/// logs.assert_logged(Level::Warn, "db", r"slow query took \d+s");
+/// ```
+pub fn capture() -> Capture {
+    let captured = Captured::new();
+
+    let guard = install(Ftail::new().capture(&captured, LevelFilter::Trace))
+        .expect("failed to install the capture logger");
+
+    Capture {
+        captured,
+        _guard: guard,
+    }
+}
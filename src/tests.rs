@@ -22,12 +22,32 @@ fn remove_datetime_from_message(message: String, config: Config) -> String {
 }
 
 // The `log` crate allow only one logger to be set. When running `cargo test` multiple tests are run concurrently.
-// This causes the logger to be set multiple times which results in a panic. To avoid this, we need to run the tests invidually.
+// To avoid setting the logger multiple times, the tests build the logger and log to it directly.
 
 #[allow(clippy::module_inception)]
 #[cfg(test)]
 mod tests {
-    use crate::{drivers::test::TestLogger, tests::remove_datetime_from_message, Config, Ftail};
+    use crate::{
+        drivers::{
+            capture::Captured,
+            gelf::Gelf,
+            http::{Http, HttpBody},
+            memory::{Capacity, MemoryBuffer, MemoryLogger},
+            otlp::Otlp,
+            single_file::SingleFileLogger,
+            stream::Stream,
+            syslog::{Facility, Syslog, SyslogFormat},
+            test::TestLogger,
+        },
+        file_format::FileFormat,
+        level::LevelHandle,
+        location::LocationFormat,
+        rate_limit::RateLimit,
+        record::FieldValue,
+        sampling::Sampling,
+        tests::remove_datetime_from_message,
+        Config, Ftail,
+    };
     use log::{Level, Log, Metadata, Record};
     use std::sync::{Arc, Mutex};
 
@@ -79,19 +99,18 @@ mod tests {
             ftail = ftail.filter_targets(targets);
         }
 
-        ftail.init().unwrap();
+        let ftail = ftail.build().unwrap();
 
         let record = Record::builder().metadata(metadata).args(args).build();
 
-        log::logger().log(&record);
+        ftail.log(&record);
 
         let logs = buffer.lock().unwrap();
 
-        return logs
-            .iter()
+        logs.iter()
             .map(|log| log.to_string())
             .collect::<Vec<String>>()
-            .join("\n");
+            .join("\n")
     }
 
     #[test]
@@ -149,7 +168,16 @@ mod tests {
 
         let message = get_message_from_logger(&config, metadata, args);
 
-        assert_eq!(message, "AEST DEBUG test Hello, world!");
+        // Sydney observes daylight saving time, so the abbreviation depends on the date
+        let abbreviation = chrono::Utc::now()
+            .with_timezone(&ftail::Tz::Australia__Sydney)
+            .format("%Z")
+            .to_string();
+
+        assert_eq!(
+            message,
+            format!("{} DEBUG test Hello, world!", abbreviation)
+        );
     }
 
     #[test]
@@ -242,4 +270,2218 @@ mod tests {
 
         assert_eq!(message, "");
     }
+
+    #[test]
+    fn it_does_not_tear_lines_when_multiple_loggers_write_to_the_same_file() {
+        let dir = std::env::temp_dir().join(format!("ftail-multi-process-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("demo.log");
+
+        let config = Config {
+            max_file_size: Some(4 * 1024),
+            multi_process: true,
+            ..Default::default()
+        };
+
+        // every logger has its own file handles, just like separate processes would
+        let handles = (0..4)
+            .map(|i| {
+                let path = path.to_str().unwrap().to_string();
+                let config = config.clone();
+
+                std::thread::spawn(move || {
+                    let logger = SingleFileLogger::new(&path, true, config).unwrap();
+
+                    for n in 0..250 {
+                        let metadata = Metadata::builder()
+                            .level(Level::Info)
+                            .target("test")
+                            .build();
+                        logger.log(
+                            &Record::builder()
+                                .metadata(metadata)
+                                .args(format_args!("writer {} message {} end", i, n))
+                                .build(),
+                        );
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut lines = Vec::new();
+
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|ext| ext == "lock") {
+                continue;
+            }
+
+            let content = std::fs::read_to_string(path).unwrap();
+            lines.extend(content.lines().map(|line| line.to_string()));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(lines.len(), 1000);
+        assert!(lines
+            .iter()
+            .all(|line| line.contains(" INFO test writer ") && line.ends_with(" end")));
+    }
+
+    #[test]
+    fn it_keeps_the_last_records_in_memory() {
+        let buffer = MemoryBuffer::new(Capacity::Records(2));
+
+        let ftail = Ftail::new()
+            .memory(&buffer, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        for message in ["one", "two", "three"] {
+            let metadata = Metadata::builder()
+                .level(Level::Info)
+                .target("test")
+                .build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        let config = Config::default();
+
+        let snapshot = buffer
+            .snapshot()
+            .into_iter()
+            .map(|message| remove_datetime_from_message(message, config.clone()))
+            .collect::<Vec<String>>();
+
+        assert_eq!(snapshot, vec!["INFO test two", "INFO test three"]);
+        assert_eq!(buffer.drain().len(), 2);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn it_keeps_the_last_bytes_in_memory() {
+        let config = Config::default();
+        let buffer = MemoryBuffer::new(Capacity::Bytes(70));
+        let logger = MemoryLogger::new(buffer.clone(), config.clone());
+
+        for message in [
+            "one",
+            "two",
+            "three",
+            "this message is too long to fit in the buffer",
+        ] {
+            let metadata = Metadata::builder()
+                .level(Level::Info)
+                .target("test")
+                .build();
+
+            logger.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        let snapshot = buffer
+            .snapshot()
+            .into_iter()
+            .map(|message| remove_datetime_from_message(message, config.clone()))
+            .collect::<Vec<String>>();
+
+        assert_eq!(snapshot, vec!["INFO test two", "INFO test three"]);
+    }
+
+    #[test]
+    fn it_can_capture_records_on_the_current_thread() {
+        let logs = crate::testing::capture();
+
+        log::warn!(target: "db", "slow query took 5s");
+        log::info!(target: "http", "GET /health");
+
+        logs.assert_logged(Level::Warn, "db", r"slow query took \d+s");
+        logs.assert_not_logged(Level::Warn, "http", ".*");
+        assert_eq!(logs.records().len(), 2);
+
+        std::thread::spawn(|| log::error!(target: "db", "from another thread"))
+            .join()
+            .unwrap();
+
+        logs.assert_not_logged(Level::Error, "db", "another thread");
+    }
+
+    #[test]
+    fn it_can_install_a_logger_on_the_current_thread() {
+        let captured = Captured::new();
+
+        let guard = crate::testing::install(
+            Ftail::new()
+                .capture(&captured, log::LevelFilter::Info)
+                .filter_targets(vec!["db"]),
+        )
+        .unwrap();
+
+        log::debug!(target: "db", "too verbose");
+        log::info!(target: "http", "other target");
+        log::info!(target: "db", "connected");
+
+        drop(guard);
+
+        log::info!(target: "db", "after the guard is dropped");
+
+        let records = captured.records();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "connected");
+        assert!(records[0].formatted.ends_with("INFO db connected"));
+    }
+
+    #[test]
+    fn it_logs_the_buffered_context_when_an_error_is_logged() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+
+        let ftail = Ftail::new()
+            .custom(
+                {
+                    let buffer = Arc::clone(&buffer);
+
+                    move |config| {
+                        Box::new(TestLogger {
+                            config,
+                            buffer: Arc::clone(&buffer),
+                        }) as Box<dyn Log + Send + Sync>
+                    }
+                },
+                log::LevelFilter::Info,
+            )
+            .with_error_context(2)
+            .build()
+            .unwrap();
+
+        let messages = [
+            (Level::Debug, "one"),
+            (Level::Trace, "two"),
+            (Level::Debug, "three"),
+            (Level::Info, "four"),
+            (Level::Error, "five"),
+            (Level::Error, "six"),
+        ];
+
+        for (level, message) in messages {
+            let metadata = Metadata::builder().level(level).target("test").build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        let config = Config::default();
+
+        let logs = buffer
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| remove_datetime_from_message(message.to_string(), config.clone()))
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            logs,
+            vec![
+                "INFO test four",
+                "TRACE test two",
+                "DEBUG test three",
+                "ERROR test five",
+                "ERROR test six",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_limits_the_number_of_messages_per_callsite() {
+        let captured = Captured::new();
+
+        let ftail = Ftail::new()
+            .capture(&captured, log::LevelFilter::Trace)
+            .rate_limit(RateLimit::per_callsite(2, 0.0))
+            .build()
+            .unwrap();
+
+        for line in [1, 1, 1, 2, 1, 2] {
+            let metadata = Metadata::builder()
+                .level(Level::Info)
+                .target("test")
+                .build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("retrying"))
+                    .file(Some("main.rs"))
+                    .line(Some(line))
+                    .build(),
+            );
+        }
+
+        let lines = captured
+            .records()
+            .iter()
+            .map(|record| record.line.unwrap())
+            .collect::<Vec<u32>>();
+
+        assert_eq!(lines, vec![1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn it_collapses_consecutive_identical_messages_per_driver() {
+        let captured = Captured::new();
+        let unfiltered = Captured::new();
+
+        let ftail = Ftail::new()
+            .capture(&captured, log::LevelFilter::Trace)
+            .with_suppress_duplicates()
+            .capture(&unfiltered, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        for message in [
+            "failed",
+            "failed",
+            "failed",
+            "recovered",
+            "failed",
+            "failed",
+        ] {
+            let metadata = Metadata::builder()
+                .level(Level::Warn)
+                .target("test")
+                .build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        ftail.flush();
+
+        let messages = captured
+            .records()
+            .into_iter()
+            .map(|record| record.message)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "failed",
+                "last message repeated 2 times",
+                "recovered",
+                "failed",
+                "last message repeated once",
+            ]
+        );
+        assert_eq!(unfiltered.records().len(), 6);
+    }
+
+    #[test]
+    fn it_does_not_hold_the_duplicate_lock_while_logging() {
+        use crate::rate_limit::RateLimiter;
+
+        let limiter = RateLimiter::new(None, true);
+        let messages = std::cell::RefCell::new(Vec::new());
+
+        // a driver that logs from `log` re-enters the limiter on the same thread
+        let log = |record: &Record| {
+            messages.borrow_mut().push(record.args().to_string());
+            limiter.flush(|_| {});
+        };
+
+        for message in ["failed", "failed", "recovered"] {
+            limiter.log(
+                &Record::builder()
+                    .level(Level::Warn)
+                    .target("test")
+                    .args(format_args!("{}", message))
+                    .build(),
+                log,
+            );
+        }
+
+        assert_eq!(
+            *messages.borrow(),
+            vec!["failed", "last message repeated once", "recovered"]
+        );
+    }
+
+    #[test]
+    fn it_reports_ongoing_duplicates_with_the_time_of_the_last_one() {
+        use crate::{rate_limit::RateLimiter, timestamp::Timestamp};
+
+        let limiter = RateLimiter::new(None, true);
+        let messages = std::cell::RefCell::new(Vec::new());
+        let start = Timestamp::now();
+
+        let log = |record: &Record| {
+            let elapsed = Timestamp::current().instant - start.instant;
+            messages
+                .borrow_mut()
+                .push((elapsed.as_secs(), record.args().to_string()));
+        };
+
+        for (seconds, message) in [
+            (0, "failed"),
+            (10, "failed"),
+            (31, "failed"),
+            (40, "failed"),
+        ] {
+            let timestamp = Timestamp {
+                instant: start.instant + std::time::Duration::from_secs(seconds),
+                ..start
+            };
+
+            timestamp.scope(|| {
+                limiter.log(
+                    &Record::builder()
+                        .level(Level::Error)
+                        .target("test")
+                        .args(format_args!("{}", message))
+                        .build(),
+                    log,
+                )
+            });
+        }
+
+        limiter.flush(log);
+
+        assert_eq!(
+            *messages.borrow(),
+            vec![
+                (0, "failed".to_string()),
+                (31, "last message repeated 2 times".to_string()),
+                (40, "last message repeated once".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_samples_messages_per_level_and_target() {
+        let sampled_messages = || {
+            let captured = Captured::new();
+
+            let ftail = Ftail::new()
+                .capture(&captured, log::LevelFilter::Trace)
+                .sampling(
+                    Sampling::new()
+                        .level(Level::Debug, 0.0)
+                        .level(Level::Info, 0.5)
+                        .target("audit", Level::Info, 1.0)
+                        .seed(42),
+                )
+                .build()
+                .unwrap();
+
+            for (level, target) in [
+                (Level::Debug, "app"),
+                (Level::Info, "app"),
+                (Level::Warn, "app"),
+                (Level::Info, "audit"),
+                (Level::Info, "audit::login"),
+            ] {
+                for n in 0..1000 {
+                    let metadata = Metadata::builder().level(level).target(target).build();
+
+                    ftail.log(
+                        &Record::builder()
+                            .metadata(metadata)
+                            .args(format_args!("{}", n))
+                            .build(),
+                    );
+                }
+            }
+
+            captured
+                .records()
+                .into_iter()
+                .map(|record| (record.level, record.target, record.message))
+                .collect::<Vec<_>>()
+        };
+
+        let records = sampled_messages();
+        let count = |level: Level, target: &str| {
+            records
+                .iter()
+                .filter(|(l, t, _)| *l == level && t == target)
+                .count()
+        };
+
+        assert_eq!(count(Level::Debug, "app"), 0);
+        assert!((400..600).contains(&count(Level::Info, "app")));
+        assert_eq!(count(Level::Warn, "app"), 1000);
+        assert_eq!(count(Level::Info, "audit"), 1000);
+        assert_eq!(count(Level::Info, "audit::login"), 1000);
+        assert_eq!(records, sampled_messages());
+    }
+
+    #[test]
+    fn it_filters_messages_per_driver() {
+        let audit = Captured::new();
+        let console = Captured::new();
+        let errors = Captured::new();
+
+        let ftail = Ftail::new()
+            .capture(&audit, log::LevelFilter::Info)
+            .with_targets(vec!["audit"])
+            .capture(&console, log::LevelFilter::Trace)
+            .with_excluded_targets(vec!["audit"])
+            .with_filter(|metadata| metadata.target() != "health")
+            .capture(&errors, log::LevelFilter::Trace)
+            .with_levels(vec![Level::Error])
+            .build()
+            .unwrap();
+
+        let messages = [
+            (Level::Info, "audit"),
+            (Level::Debug, "audit"),
+            (Level::Info, "app"),
+            (Level::Info, "health"),
+            (Level::Error, "app"),
+        ];
+
+        for (level, target) in messages {
+            let metadata = Metadata::builder().level(level).target(target).build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("message"))
+                    .build(),
+            );
+        }
+
+        let targets = |captured: &Captured| {
+            captured
+                .records()
+                .into_iter()
+                .map(|record| format!("{} {}", record.level, record.target))
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(targets(&audit), vec!["INFO audit"]);
+        assert_eq!(targets(&console), vec!["INFO app", "ERROR app"]);
+        assert_eq!(targets(&errors), vec!["ERROR app"]);
+    }
+
+    #[test]
+    fn it_matches_targets_by_module_prefix_and_glob() {
+        let captured = Captured::new();
+
+        let ftail = Ftail::new()
+            .capture(&captured, log::LevelFilter::Trace)
+            .filter_targets(vec!["my_crate", "hyper*"])
+            .exclude_targets(vec!["my_crate::*::noisy", "hyper_util"])
+            .build()
+            .unwrap();
+
+        let targets = [
+            "my_crate",
+            "my_crate::db",
+            "my_crate_other",
+            "my_crate::db::noisy",
+            "hyper",
+            "hyper_util",
+            "hyper_util::client",
+            "other",
+        ];
+
+        for target in targets {
+            let metadata = Metadata::builder()
+                .level(Level::Info)
+                .target(target)
+                .build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("message"))
+                    .build(),
+            );
+        }
+
+        let logged = captured
+            .records()
+            .into_iter()
+            .map(|record| record.target)
+            .collect::<Vec<String>>();
+
+        assert_eq!(logged, vec!["my_crate", "my_crate::db", "hyper"]);
+    }
+
+    #[test]
+    fn it_filters_messages_with_closures_and_regular_expressions() {
+        let captured = Captured::new();
+        let unfiltered = Captured::new();
+
+        let ftail = Ftail::new()
+            .capture(&captured, log::LevelFilter::Trace)
+            .capture(&unfiltered, log::LevelFilter::Trace)
+            .with_record_filter(|record| record.level() != Level::Trace)
+            .filter(|metadata| metadata.target() != "noisy")
+            .filter_record(|record| record.args().to_string() != "ping")
+            .exclude_messages(vec![r"^GET /health"])
+            .build()
+            .unwrap();
+
+        let messages = [
+            (Level::Info, "http", "GET /health 200"),
+            (Level::Info, "http", "GET /users 200"),
+            (Level::Info, "noisy", "GET /users 200"),
+            (Level::Info, "http", "ping"),
+            (Level::Trace, "http", "pong"),
+        ];
+
+        for (level, target, message) in messages {
+            let metadata = Metadata::builder().level(level).target(target).build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        let messages = |captured: &Captured| {
+            captured
+                .records()
+                .into_iter()
+                .map(|record| record.message)
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(messages(&captured), vec!["GET /users 200", "pong"]);
+        assert_eq!(messages(&unfiltered), vec!["GET /users 200"]);
+        assert!(Ftail::new()
+            .capture(&captured, log::LevelFilter::Trace)
+            .filter_messages(vec!["("])
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn it_computes_the_max_level_from_the_drivers() {
+        let max_level = |ftail: Ftail| ftail.build().unwrap().max_level.get();
+
+        let captured = Captured::new();
+
+        assert_eq!(
+            max_level(
+                Ftail::new()
+                    .capture(&captured, log::LevelFilter::Info)
+                    .capture(&captured, log::LevelFilter::Warn)
+            ),
+            log::LevelFilter::Info
+        );
+        assert_eq!(
+            max_level(Ftail::new().capture(&captured, log::LevelFilter::Off)),
+            log::LevelFilter::Trace
+        );
+        assert_eq!(
+            max_level(
+                Ftail::new()
+                    .capture(&captured, log::LevelFilter::Info)
+                    .with_error_context(10)
+            ),
+            log::LevelFilter::Trace
+        );
+        assert_eq!(
+            max_level(
+                Ftail::new()
+                    .capture(&captured, log::LevelFilter::Trace)
+                    .filter_levels(vec![Level::Error, Level::Warn])
+            ),
+            log::LevelFilter::Warn
+        );
+    }
+
+    #[test]
+    fn it_can_change_the_level_of_a_driver_at_runtime() {
+        let captured = Captured::new();
+        let handle = LevelHandle::new(log::LevelFilter::Info);
+
+        let ftail = Ftail::new()
+            .capture(&captured, log::LevelFilter::Info)
+            .with_level_handle(&handle)
+            .capture(&captured, log::LevelFilter::Warn)
+            .build()
+            .unwrap();
+
+        let log_debug = || {
+            let metadata = Metadata::builder()
+                .level(Level::Debug)
+                .target("test")
+                .build();
+
+            ftail.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("debug"))
+                    .build(),
+            );
+        };
+
+        assert_eq!(ftail.max_level.get(), log::LevelFilter::Info);
+        log_debug();
+        assert!(captured.records().is_empty());
+
+        handle.set(log::LevelFilter::Debug);
+
+        assert_eq!(ftail.max_level.get(), log::LevelFilter::Debug);
+        log_debug();
+        assert_eq!(captured.records().len(), 1);
+    }
+
+    #[test]
+    fn it_does_not_cache_datetime_formats_with_fractional_seconds() {
+        let config = Config {
+            datetime_format: "%H:%M:%S%.9f".to_string(),
+            ..Default::default()
+        };
+
+        let buffer = MemoryBuffer::new(Capacity::Records(2));
+        let logger = MemoryLogger::new(buffer.clone(), config);
+
+        for _ in 0..2 {
+            let metadata = Metadata::builder()
+                .level(Level::Info)
+                .target("test")
+                .build();
+
+            logger.log(
+                &Record::builder()
+                    .metadata(metadata)
+                    .args(format_args!("message"))
+                    .build(),
+            );
+
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let records = buffer.snapshot();
+
+        assert_eq!(records.len(), 2);
+        assert_ne!(records[0], records[1]);
+    }
+
+    #[test]
+    fn it_does_not_cache_the_rfc3339_datetime_format_per_second() {
+        use crate::timestamp::Timestamp;
+        use chrono::TimeZone;
+
+        let config = Config {
+            datetime_format: "%+".to_string(),
+            ..Default::default()
+        };
+
+        let buffer = MemoryBuffer::new(Capacity::Records(2));
+        let logger = MemoryLogger::new(buffer.clone(), config);
+
+        for nanos in [100_000_000, 200_000_000] {
+            let timestamp = Timestamp {
+                datetime: chrono::Local.timestamp_opt(1_726_594_642, nanos).unwrap(),
+                instant: std::time::Instant::now(),
+            };
+
+            timestamp.scope(|| {
+                logger.log(
+                    &Record::builder()
+                        .level(Level::Info)
+                        .target("test")
+                        .args(format_args!("message"))
+                        .build(),
+                )
+            });
+        }
+
+        let records = buffer.snapshot();
+
+        assert!(records[0].contains(":22.100"));
+        assert!(records[1].contains(":22.200"));
+    }
+
+    #[test]
+    fn it_logs_the_same_timestamp_to_all_drivers() {
+        let first = MemoryBuffer::new(Capacity::Records(1));
+        let second = MemoryBuffer::new(Capacity::Records(1));
+
+        let ftail = Ftail::new()
+            .datetime_format("%H:%M:%S%.9f")
+            .show_elapsed(true)
+            .show_uptime(true)
+            .memory(&first, log::LevelFilter::Trace)
+            .memory(&second, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder()
+            .level(Level::Info)
+            .target("test")
+            .build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("message"))
+                .build(),
+        );
+
+        let records = first.snapshot();
+
+        assert_eq!(records, second.snapshot());
+        assert!(records[0].contains("s ["));
+        assert!(records[0].contains("] INFO test message"));
+    }
+
+    #[test]
+    fn it_formats_timestamp_presets() {
+        use crate::timestamp::TimestampFormat;
+
+        let datetime =
+            chrono::DateTime::parse_from_rfc3339("2024-09-17T19:37:22.402123456+02:00").unwrap();
+
+        assert_eq!(
+            TimestampFormat::Rfc3339.format(&datetime),
+            "2024-09-17T19:37:22.402123456+02:00"
+        );
+        assert_eq!(
+            TimestampFormat::Rfc3339Utc.format(&datetime),
+            "2024-09-17T17:37:22.402123456Z"
+        );
+        assert_eq!(TimestampFormat::UnixSeconds.format(&datetime), "1726594642");
+        assert_eq!(
+            TimestampFormat::UnixMillis.format(&datetime),
+            "1726594642402"
+        );
+        assert_eq!(
+            TimestampFormat::UnixNanos.format(&datetime),
+            "1726594642402123456"
+        );
+
+        let buffer = MemoryBuffer::new(Capacity::Records(1));
+
+        let ftail = Ftail::new()
+            .timestamp_format(TimestampFormat::Rfc3339Utc)
+            .memory(&buffer, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder()
+            .level(Level::Info)
+            .target("test")
+            .build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("message"))
+                .build(),
+        );
+
+        let record = &buffer.snapshot()[0];
+        let (timestamp, message) = record.split_once(' ').unwrap();
+
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+        assert!(timestamp.ends_with('Z'));
+        assert_eq!(message, "INFO test message");
+    }
+
+    #[test]
+    fn it_names_daily_files_after_the_local_date_unless_a_timezone_is_set() {
+        let dir = std::env::temp_dir().join(format!("ftail-daily-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let local = chrono::Local::now().format("%Y-%m-%d.log").to_string();
+        let kiritimati = chrono::Utc::now()
+            .with_timezone(&crate::Tz::Pacific__Kiritimati)
+            .format("%Y-%m-%d.log")
+            .to_string();
+
+        drop(
+            Ftail::new()
+                .daily_file(dir.to_str().unwrap(), log::LevelFilter::Trace)
+                .build()
+                .unwrap(),
+        );
+
+        assert!(dir.join(&local).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        drop(
+            Ftail::new()
+                .timezone(crate::Tz::Pacific__Kiritimati)
+                .daily_file(dir.to_str().unwrap(), log::LevelFilter::Trace)
+                .build()
+                .unwrap(),
+        );
+
+        let exists = dir.join(&kiritimati).exists();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(exists);
+    }
+
+    #[test]
+    fn it_logs_with_a_fixed_offset() {
+        use crate::timestamp::TimestampFormat;
+
+        let buffer = MemoryBuffer::new(Capacity::Records(2));
+
+        let ftail = Ftail::new()
+            .fixed_offset("+02:00".parse().unwrap())
+            .timestamp_format(TimestampFormat::Rfc3339)
+            .memory(&buffer, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder()
+            .level(Level::Info)
+            .target("test")
+            .build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata.clone())
+                .args(format_args!("message"))
+                .build(),
+        );
+
+        let record = &buffer.snapshot()[0];
+        let (timestamp, _) = record.split_once(' ').unwrap();
+
+        assert!(timestamp.ends_with("+02:00"));
+
+        let config = Config {
+            datetime_format: "%z".to_string(),
+            ..Ftail::new().utc().config
+        };
+        let logger = MemoryLogger::new(buffer.clone(), config);
+
+        logger.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("message"))
+                .build(),
+        );
+
+        assert!(buffer.snapshot()[1].starts_with("+0000 INFO"));
+    }
+
+    #[test]
+    fn it_numbers_the_threads_in_the_order_they_log() {
+        let id = crate::context::thread_id();
+        let other = std::thread::spawn(crate::context::thread_id)
+            .join()
+            .unwrap();
+
+        assert_eq!(crate::context::thread_id(), id);
+        assert!(other > id);
+    }
+
+    #[test]
+    fn it_logs_the_hostname_pid_and_thread() {
+        let buffer = MemoryBuffer::new(Capacity::Records(1));
+
+        let ftail = Ftail::new()
+            .datetime_format("")
+            .show_pid(true)
+            .show_thread_name(true)
+            .show_thread_id(true)
+            .memory(&buffer, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        std::thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(move || {
+                let metadata = Metadata::builder()
+                    .level(Level::Info)
+                    .target("test")
+                    .build();
+
+                ftail.log(
+                    &Record::builder()
+                        .metadata(metadata)
+                        .args(format_args!("message"))
+                        .build(),
+                );
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let record = &buffer.snapshot()[0];
+        let prefix = format!(" pid:{} worker#", std::process::id());
+
+        assert!(record.starts_with(&prefix));
+        assert!(record.ends_with(" INFO test message"));
+    }
+
+    #[test]
+    fn it_formats_the_source_location() {
+        let buffer = MemoryBuffer::new(Capacity::Records(2));
+
+        let config = Ftail::new()
+            .datetime_format("")
+            .show_location(true)
+            .strip_location_prefix("C:\\work")
+            .normalize_separators(true)
+            .config;
+
+        let logger = MemoryLogger::new(buffer.clone(), config.clone());
+
+        let metadata = Metadata::builder()
+            .level(Level::Info)
+            .target("test")
+            .build();
+
+        logger.log(
+            &Record::builder()
+                .metadata(metadata.clone())
+                .args(format_args!("message"))
+                .module_path(Some("app::db"))
+                .file(Some("C:\\work\\crates\\app\\src/db.rs"))
+                .line(Some(42))
+                .build(),
+        );
+
+        let config = Config {
+            location_format: LocationFormat::ModulePath,
+            ..config
+        };
+
+        let logger = MemoryLogger::new(buffer.clone(), config);
+
+        logger.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("message"))
+                .module_path(Some("app::db"))
+                .file(Some("src/db.rs"))
+                .line(Some(42))
+                .build(),
+        );
+
+        let records = buffer.snapshot();
+
+        assert_eq!(records[0], " INFO test crates/app/src/db.rs:42 message");
+        assert_eq!(records[1], " INFO test app::db message");
+    }
+
+    #[test]
+    fn it_sends_rfc5424_messages_to_syslog_over_udp() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+
+        let ftail = Ftail::new()
+            .utc()
+            .syslog(
+                Syslog::udp(&address)
+                    .facility(Facility::Local0)
+                    .app_name("app")
+                    .hostname("web-1"),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder().level(Level::Warn).target("db").build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("slow query"))
+                .build(),
+        );
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+
+        // local0 (16) * 8 + warning (4)
+        assert!(message.starts_with("<132>1 "));
+        assert!(message.contains("+00:00 web-1 app "));
+        assert!(message.ends_with(&format!(" {} db - slow query", std::process::id())));
+    }
+
+    #[test]
+    fn it_does_not_block_on_a_syslog_collector_that_stops_reading() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let ftail = Ftail::new()
+            .syslog(
+                Syslog::tcp(&listener.local_addr().unwrap().to_string())
+                    .timeout(std::time::Duration::from_millis(100)),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let (done, finished) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let message = "x".repeat(1024);
+
+            for _ in 0..20_000 {
+                log_info(&ftail, &message);
+            }
+
+            done.send(()).unwrap();
+        });
+
+        assert!(finished
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .is_ok());
+
+        drop(listener);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_sends_syslog_messages_to_ipv6_collectors() {
+        let Ok(syslog) = std::net::UdpSocket::bind("[::1]:0") else {
+            return;
+        };
+
+        let ftail = Ftail::new()
+            .syslog(
+                Syslog::udp(&syslog.local_addr().unwrap().to_string()),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+
+        let mut buf = [0; 1024];
+
+        assert!(syslog.recv(&mut buf).unwrap() > 0);
+    }
+
+    #[test]
+    fn it_returns_an_error_when_a_collector_is_unreachable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let result = Ftail::new()
+            .syslog(Syslog::tcp(&address), log::LevelFilter::Trace)
+            .build();
+
+        assert!(matches!(result, Err(crate::error::FtailError::IoError(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_sends_rfc3164_messages_to_syslog_over_a_unix_socket() {
+        let path = std::env::temp_dir().join(format!("ftail-syslog-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        let ftail = Ftail::new()
+            .syslog(
+                Syslog::unix(path.to_str().unwrap())
+                    .format(SyslogFormat::Rfc3164)
+                    .app_name("app")
+                    .hostname("web-1"),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder().level(Level::Error).target("db").build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("connection lost"))
+                .build(),
+        );
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+
+        let _ = std::fs::remove_file(&path);
+
+        // user (1) * 8 + err (3)
+        assert!(message.starts_with("<11>"));
+        assert!(message.ends_with(&format!(
+            " web-1 app[{}]: connection lost",
+            std::process::id()
+        )));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_sends_structured_fields_to_journald() {
+        use crate::drivers::journald::Journald;
+
+        let path = std::env::temp_dir().join(format!("ftail-journald-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        let ftail = Ftail::new()
+            .journald(
+                Journald::new()
+                    .socket(path.to_str().unwrap())
+                    .syslog_identifier("app"),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder().level(Level::Warn).target("db").build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("slow\nquery"))
+                .file(Some("src/db.rs"))
+                .line(Some(42))
+                .key_values(&[("user-id", 7), ("priority", 0)])
+                .build(),
+        );
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+
+        let _ = std::fs::remove_file(&path);
+
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&10u64.to_le_bytes());
+        expected.extend_from_slice(
+            b"slow\nquery\nPRIORITY=4\nSYSLOG_IDENTIFIER=app\nTARGET=db\nCODE_FILE=src/db.rs\nCODE_LINE=42\nUSER_ID=7\n",
+        );
+
+        assert_eq!(&buf[..len], &expected[..]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn it_sends_large_journald_entries_through_a_memfd() {
+        use crate::drivers::journald::Journald;
+        use std::{
+            io::{Read, Seek},
+            os::fd::AsRawFd,
+        };
+
+        let path =
+            std::env::temp_dir().join(format!("ftail-journald-memfd-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        let ftail = Ftail::new()
+            .journald(
+                Journald::new().socket(path.to_str().unwrap()),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let message = "x".repeat(1024 * 1024);
+        log_info(&ftail, &message);
+
+        let mut control = [0u64; 8];
+        let mut header: libc::msghdr = unsafe { std::mem::zeroed() };
+        header.msg_control = control.as_mut_ptr().cast();
+        header.msg_controllen = std::mem::size_of_val(&control) as _;
+
+        let len = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut header, 0) };
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(len, 0);
+
+        let fd = unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&header);
+            assert_eq!((*cmsg).cmsg_type, libc::SCM_RIGHTS);
+            std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>())
+        };
+
+        let mut entry = String::new();
+        let mut file = unsafe { <std::fs::File as std::os::fd::FromRawFd>::from_raw_fd(fd) };
+        // the memfd shares the file offset with the sender, which is at the end
+        file.seek(std::io::SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut entry).unwrap();
+
+        assert!(entry.starts_with(&format!("MESSAGE={}\nPRIORITY=6\n", message)));
+    }
+
+    #[test]
+    fn it_runs_the_filter_once_per_record() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let buffer = MemoryBuffer::new(Capacity::Records(1));
+
+        let ftail = Ftail::new()
+            .filter({
+                let calls = calls.clone();
+
+                move |_| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+            })
+            .memory(&buffer, log::LevelFilter::Trace)
+            .with_filter({
+                let calls = calls.clone();
+
+                move |_| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+            })
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(buffer.snapshot().len(), 1);
+    }
+
+    fn read_lines(listener: &std::net::TcpListener, count: usize) -> Vec<String> {
+        use std::io::BufRead;
+
+        let (stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+
+        std::io::BufReader::new(stream)
+            .lines()
+            .take(count)
+            .map(|line| line.unwrap())
+            .collect()
+    }
+
+    fn log_info(ftail: &Ftail, message: &str) {
+        let metadata = Metadata::builder()
+            .level(Level::Info)
+            .target("test")
+            .build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("{}", message))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn it_streams_messages_over_tcp() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let ftail = Ftail::new()
+            .datetime_format("")
+            .stream(Stream::tcp(&address), log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "first");
+        log_info(&ftail, "second");
+        ftail.flush();
+
+        assert_eq!(
+            read_lines(&listener, 2),
+            vec![" INFO test first", " INFO test second"]
+        );
+    }
+
+    #[test]
+    fn it_buffers_and_spills_messages_until_the_peer_is_available() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let spill_path =
+            std::env::temp_dir().join(format!("ftail-spill-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&spill_path);
+
+        let ftail = Ftail::new()
+            .datetime_format("")
+            .stream(
+                Stream::tcp(&address.to_string())
+                    .capacity(1)
+                    .spill_to_disk(spill_path.to_str().unwrap())
+                    .backoff(
+                        std::time::Duration::from_millis(10),
+                        std::time::Duration::from_millis(10),
+                    ),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "first");
+        log_info(&ftail, "second");
+        log_info(&ftail, "third");
+
+        let listener = std::net::TcpListener::bind(address).unwrap();
+        let lines = read_lines(&listener, 3);
+
+        drop(ftail);
+        let _ = std::fs::remove_file(&spill_path);
+
+        assert_eq!(
+            lines,
+            vec![" INFO test first", " INFO test second", " INFO test third"]
+        );
+    }
+
+    #[test]
+    fn it_streams_messages_in_the_file_format() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let ftail = Ftail::new()
+            .stream(
+                Stream::tcp(&listener.local_addr().unwrap().to_string()),
+                log::LevelFilter::Trace,
+            )
+            .with_file_format(FileFormat::Ecs)
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+
+        let lines = read_lines(&listener, 1);
+
+        assert!(lines[0].starts_with(r#"{"@timestamp":""#));
+        assert!(lines[0].contains(r#""message":"message""#));
+    }
+
+    #[test]
+    fn it_spills_buffered_messages_when_flushed_while_disconnected() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let spill_path =
+            std::env::temp_dir().join(format!("ftail-flush-spill-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&spill_path);
+
+        let ftail = Ftail::new()
+            .datetime_format("")
+            .stream(
+                Stream::tcp(&address.to_string()).spill_to_disk(spill_path.to_str().unwrap()),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        for i in 0..5 {
+            log_info(&ftail, &format!("message {}", i));
+        }
+
+        ftail.flush();
+
+        let spilled = std::fs::read_to_string(&spill_path).unwrap();
+
+        drop(ftail);
+        let _ = std::fs::remove_file(&spill_path);
+
+        assert_eq!(spilled.lines().count(), 5);
+        assert!(spilled.ends_with(" INFO test message 4\n"));
+    }
+
+    #[test]
+    fn it_spills_buffered_messages_at_shutdown_and_replays_them_in_chunks() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let spill_path =
+            std::env::temp_dir().join(format!("ftail-shutdown-spill-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&spill_path);
+
+        let stream = Stream::tcp(&address.to_string())
+            .spill_to_disk(spill_path.to_str().unwrap())
+            .backoff(
+                std::time::Duration::from_millis(10),
+                std::time::Duration::from_millis(10),
+            );
+
+        let ftail = Ftail::new()
+            .datetime_format("")
+            .stream(stream.clone(), log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "first");
+        log_info(&ftail, "second");
+        drop(ftail);
+
+        let spilled = std::fs::read_to_string(&spill_path).unwrap();
+
+        // more than one chunk of the spill file
+        let expected = (0..2000)
+            .map(|i| format!(" INFO test spilled message number {:04}", i))
+            .collect::<Vec<_>>();
+
+        let mut spill = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&spill_path)
+            .unwrap();
+        std::io::Write::write_all(&mut spill, format!("{}\n", expected.join("\n")).as_bytes())
+            .unwrap();
+
+        let listener = std::net::TcpListener::bind(address).unwrap();
+
+        let ftail = Ftail::new()
+            .datetime_format("")
+            .stream(stream, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        let lines = read_lines(&listener, 2002);
+
+        drop(ftail);
+        let replayed_len = std::fs::metadata(&spill_path).unwrap().len();
+        let _ = std::fs::remove_file(&spill_path);
+
+        assert_eq!(spilled, " INFO test first\n INFO test second\n");
+        assert_eq!(lines[..2], [" INFO test first", " INFO test second"]);
+        assert_eq!(lines[2..], expected);
+        assert_eq!(replayed_len, 0);
+    }
+
+    #[test]
+    fn it_sends_gelf_messages_over_udp() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+
+        let ftail = Ftail::new()
+            .gelf(Gelf::udp(&address).host("web-1"), log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder().level(Level::Error).target("db").build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("query \"users\" failed"))
+                .line(Some(42))
+                .key_values(&[("user id", 7)])
+                .build(),
+        );
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+
+        assert!(message
+            .starts_with(r#"{"version":"1.1","host":"web-1","short_message":"query \"users\" failed","timestamp":"#));
+        assert!(message.ends_with(r#","level":3,"_target":"db","_line":42,"_user_id":7}"#));
+    }
+
+    #[test]
+    fn it_sends_gelf_messages_to_ipv6_inputs() {
+        let Ok(socket) = std::net::UdpSocket::bind("[::1]:0") else {
+            return;
+        };
+
+        let ftail = Ftail::new()
+            .gelf(
+                Gelf::udp(&socket.local_addr().unwrap().to_string()),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+
+        let mut buf = [0; 1024];
+
+        assert!(socket.recv(&mut buf).unwrap() > 0);
+    }
+
+    #[test]
+    fn it_sends_compressed_gelf_messages_in_chunks() {
+        use std::io::Read;
+
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+
+        let ftail = Ftail::new()
+            .gelf(
+                Gelf::udp(&address).chunk_size(32).compress(true),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder()
+            .level(Level::Info)
+            .target("test")
+            .build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("a message that doesn't fit in one chunk"))
+                .build(),
+        );
+
+        let mut chunks = Vec::new();
+
+        loop {
+            let mut buf = [0; 32];
+            let len = socket.recv(&mut buf).unwrap();
+
+            assert_eq!(&buf[..2], &[0x1e, 0x0f]);
+            assert_eq!(buf[10] as usize, chunks.len());
+
+            chunks.push(buf[12..len].to_vec());
+
+            if chunks.len() == buf[11] as usize {
+                break;
+            }
+        }
+
+        let mut message = String::new();
+        flate2::read::GzDecoder::new(&chunks.concat()[..])
+            .read_to_string(&mut message)
+            .unwrap();
+
+        assert!(message.contains(r#""short_message":"a message that doesn't fit in one chunk""#));
+    }
+
+    /// Respond to `count` requests with the status and return the request heads and bodies.
+    fn serve_http(
+        listener: std::net::TcpListener,
+        status: &'static str,
+        count: usize,
+    ) -> std::thread::JoinHandle<Vec<(String, String)>> {
+        use std::io::{BufRead, Read, Write};
+
+        std::thread::spawn(move || {
+            let mut requests = Vec::new();
+
+            for _ in 0..count {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = std::io::BufReader::new(stream);
+
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if line == "\r\n" {
+                        break;
+                    }
+
+                    head.push_str(&line);
+                }
+
+                let len = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n",
+                    status
+                )
+                .unwrap();
+
+                requests.push((head, String::from_utf8(body).unwrap()));
+            }
+
+            requests
+        })
+    }
+
+    #[test]
+    fn it_sends_ndjson_timestamps_in_the_timestamp_format() {
+        use crate::timestamp::TimestampFormat;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let server = serve_http(listener, "200 OK", 1);
+
+        let ftail = Ftail::new()
+            .timestamp_format(TimestampFormat::UnixMillis)
+            .http(Http::new(&url).batch_size(1), log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+
+        let requests = server.join().unwrap();
+        let (millis, tail) = requests[0]
+            .1
+            .strip_prefix(r#"{"timestamp":"#)
+            .unwrap()
+            .split_once(',')
+            .unwrap();
+
+        assert!(millis.parse::<i64>().unwrap() > 1_726_594_642_402);
+        assert_eq!(
+            tail,
+            "\"level\":\"INFO\",\"target\":\"test\",\"message\":\"message\"}\n"
+        );
+    }
+
+    #[test]
+    fn it_sends_batches_of_ndjson_over_http() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let server = serve_http(listener, "200 OK", 1);
+
+        let ftail = Ftail::new()
+            .utc()
+            .http(
+                Http::new(&url)
+                    .header("X-Tenant", "acme\r\nX-Injected: yes")
+                    .batch_size(2)
+                    .batch_interval(std::time::Duration::from_secs(60)),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "first");
+
+        let metadata = Metadata::builder().level(Level::Warn).target("db").build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("second"))
+                .key_values(&[("user_id", 7)])
+                .build(),
+        );
+
+        let requests = server.join().unwrap();
+        let (head, body) = &requests[0];
+
+        assert!(head.starts_with("POST /ingest HTTP/1.1\r\n"));
+        assert!(head.contains("Content-Type: application/x-ndjson\r\n"));
+        assert!(head.contains("\r\nX-Tenant: acmeX-Injected: yes\r\n"));
+        assert!(!head.contains("\r\nX-Injected"));
+
+        let lines: Vec<&str> = body.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"timestamp":""#));
+        assert!(lines[0].ends_with(r#"+00:00","level":"INFO","target":"test","message":"first"}"#));
+        assert!(
+            lines[1].ends_with(r#","level":"WARN","target":"db","message":"second","user_id":7}"#)
+        );
+    }
+
+    #[test]
+    fn it_sends_batches_to_ipv6_literals() {
+        let Ok(listener) = std::net::TcpListener::bind("[::1]:0") else {
+            return;
+        };
+        let port = listener.local_addr().unwrap().port();
+        let server = serve_http(listener, "200 OK", 1);
+
+        let ftail = Ftail::new()
+            .http(
+                Http::new(&format!("http://[::1]:{}/ingest", port)),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+        ftail.flush();
+
+        let requests = server.join().unwrap();
+
+        assert!(requests[0]
+            .0
+            .contains(&format!("\r\nHost: [::1]:{}\r\n", port)));
+    }
+
+    #[test]
+    fn it_retries_and_writes_undelivered_batches_to_the_dead_letter_file() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/loki/api/v1/push", listener.local_addr().unwrap());
+        let server = serve_http(listener, "503 Service Unavailable", 2);
+
+        let dead_letter =
+            std::env::temp_dir().join(format!("ftail-dead-letter-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&dead_letter);
+
+        let ftail = Ftail::new()
+            .http(
+                Http::new(&url)
+                    .body(HttpBody::Loki)
+                    .retries(1)
+                    .backoff(
+                        std::time::Duration::from_millis(10),
+                        std::time::Duration::from_millis(10),
+                    )
+                    .dead_letter(dead_letter.to_str().unwrap()),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+        ftail.flush();
+
+        let requests = server.join().unwrap();
+        let contents = std::fs::read_to_string(&dead_letter).unwrap();
+        let _ = std::fs::remove_file(&dead_letter);
+
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1, requests[1].1);
+        assert_eq!(contents, format!("{}\n", requests[0].1));
+        assert!(contents
+            .starts_with(r#"{"streams":[{"stream":{"level":"info","target":"test"},"values":[[""#));
+        assert!(contents.ends_with("\",\"message\"]]}]}\n"));
+    }
+
+    #[test]
+    fn it_reports_records_dropped_from_a_full_http_buffer() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let server = serve_http(listener, "200 OK", 2);
+
+        let dead_letter =
+            std::env::temp_dir().join(format!("ftail-dropped-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&dead_letter);
+
+        let http = Http::new(&url)
+            .capacity(1)
+            .batch_interval(std::time::Duration::from_secs(60));
+
+        for http in [
+            http.clone(),
+            http.dead_letter(dead_letter.to_str().unwrap()),
+        ] {
+            let ftail = Ftail::new()
+                .http(http, log::LevelFilter::Trace)
+                .build()
+                .unwrap();
+
+            log_info(&ftail, "first");
+            log_info(&ftail, "second");
+            ftail.flush();
+        }
+
+        let requests = server.join().unwrap();
+        let contents = std::fs::read_to_string(&dead_letter).unwrap();
+        let _ = std::fs::remove_file(&dead_letter);
+
+        let counted: Vec<&str> = requests[0].1.lines().collect();
+
+        assert_eq!(counted.len(), 2);
+        assert!(counted[0].ends_with(r#""message":"second"}"#));
+        assert!(counted[1].ends_with(
+            r#""level":"WARN","target":"ftail","message":"1 record was dropped because the buffer was full"}"#
+        ));
+        assert_eq!(requests[1].1.lines().count(), 1);
+        assert!(requests[1].1.ends_with("\"message\":\"second\"}\n"));
+        assert!(contents.ends_with("\"message\":\"first\"}\n"));
+    }
+
+    #[test]
+    fn it_writes_otlp_json_lines() {
+        let path = std::env::temp_dir().join(format!("ftail-otlp-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let ftail = Ftail::new()
+            .otlp_file(
+                path.to_str().unwrap(),
+                Otlp::new("app")
+                    .host_name("web-1")
+                    .attribute("service.version", "1.2.0"),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder().level(Level::Warn).target("db").build();
+
+        ftail.log(
+            &Record::builder()
+                .metadata(metadata)
+                .args(format_args!("slow query"))
+                .file(Some("src/db.rs"))
+                .line(Some(42))
+                .key_values(&[("duration_ms", 1500)])
+                .build(),
+        );
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let (head, tail) = contents.split_once(r#"{"timeUnixNano":""#).unwrap();
+        let (time, tail) = tail.split_once('"').unwrap();
+
+        assert_eq!(
+            head,
+            concat!(
+                r#"{"resourceLogs":[{"resource":{"attributes":["#,
+                r#"{"key":"service.name","value":{"stringValue":"app"}},"#,
+                r#"{"key":"host.name","value":{"stringValue":"web-1"}},"#,
+                r#"{"key":"service.version","value":{"stringValue":"1.2.0"}}]},"#,
+                r#""scopeLogs":[{"scope":{"name":"db"},"logRecords":["#
+            )
+        );
+        assert!(time.parse::<u128>().is_ok());
+        assert_eq!(
+            tail,
+            format!(
+                concat!(
+                    r#","observedTimeUnixNano":"{}","severityNumber":13,"severityText":"WARN","#,
+                    r#""body":{{"stringValue":"slow query"}},"attributes":["#,
+                    r#"{{"key":"code.filepath","value":{{"stringValue":"src/db.rs"}}}},"#,
+                    r#"{{"key":"code.lineno","value":{{"intValue":"42"}}}},"#,
+                    r#"{{"key":"duration_ms","value":{{"intValue":"1500"}}}}]}}]}}]}}]}}"#,
+                    "\n"
+                ),
+                time
+            )
+        );
+    }
+
+    #[test]
+    fn it_writes_ecs_json_lines() {
+        let path = std::env::temp_dir().join(format!("ftail-ecs-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let ftail = Ftail::new()
+            .file_format(FileFormat::Ecs)
+            .single_file(path.to_str().unwrap(), false, log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        let metadata = Metadata::builder().level(Level::Error).target("db").build();
+
+        std::thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(move || {
+                ftail.log(
+                    &Record::builder()
+                        .metadata(metadata)
+                        .args(format_args!("query \"users\" failed"))
+                        .file(Some("src/db.rs"))
+                        .line(Some(42))
+                        .key_values(&[("retries", 3)])
+                        .build(),
+                );
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let (timestamp, tail) = contents
+            .strip_prefix(r#"{"@timestamp":""#)
+            .unwrap()
+            .split_once('"')
+            .unwrap();
+
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+        assert!(timestamp.ends_with('Z'));
+        assert_eq!(
+            tail,
+            format!(
+                concat!(
+                    r#","log.level":"error","message":"query \"users\" failed","#,
+                    r#""ecs.version":"1.6.0","log.logger":"db","#,
+                    r#""log.origin.file.name":"src/db.rs","log.origin.file.line":42,"#,
+                    r#""process.pid":{},"process.thread.name":"worker","#,
+                    r#""host.hostname":"{}","labels":{{"retries":3}}}}"#,
+                    "\n"
+                ),
+                std::process::id(),
+                gethostname::gethostname().to_string_lossy()
+            )
+        );
+    }
+
+    #[test]
+    fn it_sets_the_file_format_per_driver() {
+        let dir = std::env::temp_dir();
+        let text = dir.join(format!("ftail-text-{}.log", std::process::id()));
+        let ecs = dir.join(format!("ftail-ecs-driver-{}.log", std::process::id()));
+
+        let ftail = Ftail::new()
+            .single_file(text.to_str().unwrap(), false, log::LevelFilter::Trace)
+            .single_file(ecs.to_str().unwrap(), false, log::LevelFilter::Trace)
+            .with_file_format(FileFormat::Ecs)
+            .build()
+            .unwrap();
+
+        log_info(&ftail, "message");
+        drop(ftail);
+
+        let text_contents = std::fs::read_to_string(&text).unwrap();
+        let ecs_contents = std::fs::read_to_string(&ecs).unwrap();
+        let _ = std::fs::remove_file(&text);
+        let _ = std::fs::remove_file(&ecs);
+
+        assert!(text_contents.ends_with("message\n"));
+        assert!(!text_contents.starts_with('{'));
+        assert!(ecs_contents.starts_with(r#"{"@timestamp":"#));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn it_inserts_records_into_sqlite_and_applies_the_retention_policy() {
+        use crate::drivers::sqlite::Sqlite;
+
+        let path = std::env::temp_dir().join(format!("ftail-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let ftail = Ftail::new()
+            .sqlite(
+                Sqlite::new(path.to_str().unwrap()).batch_size(2),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        for i in 0..3 {
+            ftail.log(
+                &Record::builder()
+                    .level(Level::Warn)
+                    .target("db")
+                    .args(format_args!("query {}", i))
+                    .file(Some("src/db.rs"))
+                    .line(Some(42))
+                    .key_values(&[("retries", i)])
+                    .build(),
+            );
+        }
+
+        ftail.flush();
+        drop(ftail);
+
+        let ftail = Ftail::new()
+            .sqlite(
+                Sqlite::new(path.to_str().unwrap()).max_rows(2),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+
+        let journal_mode: String = connection
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+
+        let rows = connection
+            .prepare("SELECT level, target, message, file, line, key_values, timestamp FROM logs")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, u32>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        drop(ftail);
+        drop(connection);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+
+        assert_eq!(journal_mode, "wal");
+        assert_eq!(rows.len(), 2);
+
+        for (i, row) in rows.iter().enumerate() {
+            let i = i + 1;
+
+            assert_eq!(row.0, "WARN");
+            assert_eq!(row.1, "db");
+            assert_eq!(row.2, format!("query {}", i));
+            assert_eq!(row.3, "src/db.rs");
+            assert_eq!(row.4, 42);
+            assert_eq!(row.5, format!(r#"{{"retries":{}}}"#, i));
+            assert!(chrono::DateTime::parse_from_rfc3339(&row.6).is_ok());
+        }
+    }
+
+    #[test]
+    fn it_forwards_owned_records_to_a_channel_and_a_callback() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+
+        let ftail = {
+            let warnings = warnings.clone();
+
+            Ftail::new()
+                .channel(sender, log::LevelFilter::Info)
+                .callback(
+                    move |record| warnings.lock().unwrap().push(record.message),
+                    log::LevelFilter::Warn,
+                )
+                .build()
+                .unwrap()
+        };
+
+        for (level, message) in [
+            (Level::Debug, "debug"),
+            (Level::Info, "info"),
+            (Level::Warn, "warn"),
+        ] {
+            ftail.log(
+                &Record::builder()
+                    .level(level)
+                    .target("app")
+                    .args(format_args!("{}", message))
+                    .module_path(Some("app::db"))
+                    .file(Some("src/db.rs"))
+                    .line(Some(42))
+                    .key_values(&[("user_id", 42)])
+                    .build(),
+            );
+        }
+
+        drop(ftail);
+
+        let records = receiver.iter().collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level, Level::Info);
+        assert_eq!(records[0].target, "app");
+        assert_eq!(records[0].message, "info");
+        assert_eq!(records[0].module_path.as_deref(), Some("app::db"));
+        assert_eq!(records[0].file.as_deref(), Some("src/db.rs"));
+        assert_eq!(records[0].line, Some(42));
+        assert_eq!(
+            records[0].key_values,
+            vec![("user_id".to_string(), FieldValue::I64(42))]
+        );
+        assert_eq!(records[0].key_values[0].1.to_string(), "42");
+        assert_eq!(records[1].message, "warn");
+        assert_eq!(*warnings.lock().unwrap(), vec!["warn".to_string()]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn it_deletes_sqlite_records_older_than_the_maximum_age() {
+        use crate::drivers::sqlite::Sqlite;
+        use std::time::Duration;
+
+        let path = std::env::temp_dir().join(format!("ftail-max-age-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let sqlite = Sqlite::new(path.to_str().unwrap());
+
+        let ftail = Ftail::new()
+            .sqlite(sqlite.clone(), log::LevelFilter::Trace)
+            .build()
+            .unwrap();
+
+        ftail.log(
+            &Record::builder()
+                .level(Level::Info)
+                .target("app")
+                .args(format_args!("recent"))
+                .build(),
+        );
+
+        ftail.flush();
+        drop(ftail);
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute(
+                "INSERT INTO logs (timestamp, level, target, message)
+                VALUES ('2000-01-01T00:00:00.000000Z', 'INFO', 'app', 'old')",
+                [],
+            )
+            .unwrap();
+
+        let messages = || {
+            connection
+                .prepare("SELECT message FROM logs ORDER BY id")
+                .unwrap()
+                .query_map([], |row| row.get::<_, String>(0))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+
+        // an age beyond the range of dates keeps every record
+        Ftail::new()
+            .sqlite(
+                sqlite.clone().max_age(Duration::MAX),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let unlimited = messages();
+
+        Ftail::new()
+            .sqlite(
+                sqlite.max_age(Duration::from_secs(3600)),
+                log::LevelFilter::Trace,
+            )
+            .build()
+            .unwrap();
+
+        let retained = messages();
+
+        drop(connection);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+
+        assert_eq!(unlimited, vec!["recent", "old"]);
+        assert_eq!(retained, vec!["recent"]);
+    }
 }
@@ -0,0 +1,138 @@
+use std::{
+    cell::Cell,
+    fmt::{Display, Write},
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// A timestamp format that replaces the strftime `datetime_format`, so the logged times can be
+/// parsed without knowing the format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 with nanoseconds and the offset of the timezone, e.g.
+    /// `2024-09-17T19:37:22.402123456+02:00`.
+    Rfc3339,
+    /// RFC 3339 with nanoseconds in UTC, e.g. `2024-09-17T17:37:22.402123456Z`.
+    Rfc3339Utc,
+    /// Seconds since the Unix epoch, e.g. `1726594642`.
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch, e.g. `1726594642402`.
+    UnixMillis,
+    /// Nanoseconds since the Unix epoch, e.g. `1726594642402123456`.
+    UnixNanos,
+}
+
+impl TimestampFormat {
+    /// Format the datetime, e.g. for a custom driver. [`TimestampFormat::Rfc3339`] uses the
+    /// offset of the datetime.
+    pub fn format<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let mut result = String::new();
+        self.write(datetime, &mut result).unwrap();
+
+        result
+    }
+
+    pub(crate) fn write<Tz: TimeZone>(
+        &self,
+        datetime: &DateTime<Tz>,
+        w: &mut impl Write,
+    ) -> std::fmt::Result
+    where
+        Tz::Offset: Display,
+    {
+        match self {
+            TimestampFormat::Rfc3339 => {
+                write!(w, "{}", datetime.format("%Y-%m-%dT%H:%M:%S%.9f%:z"))
+            }
+            TimestampFormat::Rfc3339Utc => write!(
+                w,
+                "{}",
+                datetime
+                    .with_timezone(&Utc)
+                    .format("%Y-%m-%dT%H:%M:%S%.9fZ")
+            ),
+            TimestampFormat::UnixSeconds => write!(w, "{}", datetime.timestamp()),
+            TimestampFormat::UnixMillis => write!(w, "{}", datetime.timestamp_millis()),
+            TimestampFormat::UnixNanos => write!(
+                w,
+                "{}",
+                datetime.timestamp() as i128 * 1_000_000_000
+                    + datetime.timestamp_subsec_nanos() as i128
+            ),
+        }
+    }
+
+    /// Whether the formatted timestamp only changes once per second.
+    pub(crate) fn is_cacheable_per_second(&self) -> bool {
+        *self == TimestampFormat::UnixSeconds
+    }
+}
+
+/// The time a record was logged, captured once so every driver shows the same time.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Timestamp {
+    pub datetime: chrono::DateTime<chrono::Local>,
+    pub instant: Instant,
+}
+
+thread_local! {
+    static CURRENT: Cell<Option<Timestamp>> = const { Cell::new(None) };
+}
+
+static STARTUP: OnceLock<Timestamp> = OnceLock::new();
+
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Timestamp {
+            datetime: chrono::Local::now(),
+            instant: Instant::now(),
+        }
+    }
+
+    /// The timestamp of the record being logged on the current thread, or the current time when
+    /// a driver is used on its own.
+    pub(crate) fn current() -> Self {
+        CURRENT
+            .with(|current| current.get())
+            .unwrap_or_else(Timestamp::now)
+    }
+
+    /// The time the first logger was initialized.
+    pub(crate) fn startup() -> Self {
+        *STARTUP.get_or_init(Timestamp::now)
+    }
+
+    /// Make this the timestamp of the records logged on the current thread while `f` runs.
+    pub(crate) fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let _previous = Restore(CURRENT.with(|current| current.replace(Some(self))));
+
+        f()
+    }
+
+    /// The wall-clock time since startup, matching the datetimes of the records.
+    pub(crate) fn elapsed(&self) -> Duration {
+        (self.datetime - Timestamp::startup().datetime)
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// The monotonic time since startup, which isn't affected by changes to the system clock.
+    pub(crate) fn uptime(&self) -> Duration {
+        self.instant
+            .saturating_duration_since(Timestamp::startup().instant)
+    }
+}
+
+/// Restores the previous timestamp of the current thread, also when a driver panics.
+struct Restore(Option<Timestamp>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.0));
+    }
+}
@@ -1,47 +1,342 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    fmt::{Display, Write},
+    thread::Thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
 use log::Record;
 
-use crate::Config;
+use crate::{
+    context,
+    location::LocationFormat,
+    timestamp::{Timestamp, TimestampFormat},
+    Config,
+};
+
+/// The last formatted datetime of the current thread, reused while the second doesn't change or,
+/// for formats with fractional seconds, for the same timestamp.
+struct DatetimeCache {
+    timestamp: (i64, u32),
+    datetime_format: String,
+    timestamp_format: Option<TimestampFormat>,
+    utc_offset: Option<FixedOffset>,
+    #[cfg(feature = "timezone")]
+    timezone: chrono_tz::Tz,
+    formatted: String,
+}
+
+thread_local! {
+    static DATETIME_CACHE: RefCell<Option<DatetimeCache>> = const { RefCell::new(None) };
+}
 
 pub(crate) struct LogWriter<'a> {
     record: &'a Record<'a>,
     config: &'a Config,
+    timestamp: Timestamp,
+    thread: OnceCell<Thread>,
 }
 
 impl<'a> LogWriter<'a> {
     pub fn new(record: &'a Record<'a>, config: &'a Config) -> LogWriter<'a> {
-        LogWriter { record, config }
+        LogWriter {
+            record,
+            config,
+            timestamp: Timestamp::current(),
+            thread: OnceCell::new(),
+        }
+    }
+
+    pub fn get_datetime(&self) -> Datetime<'_, 'a> {
+        Datetime { writer: self }
+    }
+
+    pub fn get_utc_datetime(&self) -> DateTime<Utc> {
+        self.timestamp.datetime.with_timezone(&Utc)
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        self.timestamp.elapsed()
     }
 
-    pub fn get_datetime(&self) -> String {
-        #[cfg(not(feature = "timezone"))]
-        return chrono::Local::now()
-            .format(&self.config.datetime_format)
-            .to_string();
+    pub fn get_uptime(&self) -> Duration {
+        self.timestamp.uptime()
+    }
+
+    fn write_datetime(&self, w: &mut impl Write) -> std::fmt::Result {
+        let now = self.timestamp.datetime;
+
+        let per_second = match self.config.timestamp_format {
+            Some(timestamp_format) => timestamp_format.is_cacheable_per_second(),
+            None => is_cacheable_per_second(&self.config.datetime_format),
+        };
+
+        let timestamp = if per_second {
+            (now.timestamp(), 0)
+        } else {
+            (now.timestamp(), now.timestamp_subsec_nanos())
+        };
+
+        DATETIME_CACHE.with(|cache| {
+            let mut cache = match cache.try_borrow_mut() {
+                Ok(cache) => cache,
+                Err(_) => return self.format_datetime(now, w),
+            };
+
+            let is_cached = cache.as_ref().is_some_and(|cache| {
+                cache.timestamp == timestamp
+                    && cache.datetime_format == self.config.datetime_format
+                    && cache.timestamp_format == self.config.timestamp_format
+                    && cache.utc_offset == self.config.utc_offset
+                    && self.is_cached_timezone(cache)
+            });
 
-        #[cfg(feature = "timezone")]
-        return chrono::Local::now()
-            .with_timezone(&self.config.timezone)
-            .format(&self.config.datetime_format)
-            .to_string();
+            if !is_cached {
+                let mut formatted = String::new();
+                self.format_datetime(now, &mut formatted)?;
+
+                *cache = Some(DatetimeCache {
+                    timestamp,
+                    datetime_format: self.config.datetime_format.clone(),
+                    timestamp_format: self.config.timestamp_format,
+                    utc_offset: self.config.utc_offset,
+                    #[cfg(feature = "timezone")]
+                    timezone: self.config.timezone,
+                    formatted,
+                });
+            }
+
+            w.write_str(&cache.as_ref().unwrap().formatted)
+        })
     }
 
-    pub fn get_level(&self) -> String {
-        self.record.level().to_string()
+    #[cfg(feature = "timezone")]
+    fn is_cached_timezone(&self, cache: &DatetimeCache) -> bool {
+        cache.timezone == self.config.timezone
+    }
+
+    #[cfg(not(feature = "timezone"))]
+    fn is_cached_timezone(&self, _cache: &DatetimeCache) -> bool {
+        true
+    }
+
+    fn format_datetime(&self, now: DateTime<Local>, w: &mut impl Write) -> std::fmt::Result {
+        write_datetime(
+            now,
+            self.config,
+            self.config.timestamp_format,
+            &self.config.datetime_format,
+            w,
+        )
+    }
+
+    pub fn get_thread_name(&self) -> Option<&str> {
+        self.thread.get_or_init(context::thread).name()
+    }
+
+    pub fn get_thread_id(&self) -> u64 {
+        context::thread_id()
+    }
+
+    pub fn get_pid(&self) -> u32 {
+        context::pid()
+    }
+
+    pub fn get_hostname(&self) -> &'static str {
+        context::hostname()
+    }
+
+    /// The enabled hostname, process id and thread fields, separated by spaces.
+    pub fn get_context(&self) -> Context<'_, 'a> {
+        Context { writer: self }
+    }
+
+    pub fn has_context(&self) -> bool {
+        self.config.show_hostname
+            || self.config.show_pid
+            || self.config.show_thread_name
+            || self.config.show_thread_id
+    }
+
+    fn write_context(&self, w: &mut impl Write) -> std::fmt::Result {
+        let mut separator = "";
+
+        if self.config.show_hostname {
+            write!(w, "{}", self.get_hostname())?;
+            separator = " ";
+        }
+
+        if self.config.show_pid {
+            write!(w, "{}pid:{}", separator, self.get_pid())?;
+            separator = " ";
+        }
+
+        if self.config.show_thread_name || self.config.show_thread_id {
+            w.write_str(separator)?;
+        }
+
+        if self.config.show_thread_name {
+            w.write_str(self.get_thread_name().unwrap_or("<unnamed>"))?;
+        }
+
+        if self.config.show_thread_id {
+            write!(w, "#{}", self.get_thread_id())?;
+        }
+
+        Ok(())
     }
 
-    pub fn get_target(&self) -> String {
-        self.record.target().to_string()
+    pub fn get_level(&self) -> log::Level {
+        self.record.level()
     }
 
-    pub fn get_args(&self) -> String {
-        self.record.args().to_string()
+    pub fn get_target(&self) -> &str {
+        self.record.target()
     }
 
-    pub fn get_file(&self) -> Option<String> {
-        self.record.file().map(|f| f.to_string())
+    pub fn get_args(&self) -> &std::fmt::Arguments<'a> {
+        self.record.args()
+    }
+
+    pub fn get_file(&self) -> Option<&str> {
+        self.record.file()
     }
 
     pub fn get_line(&self) -> Option<u32> {
         self.record.line()
     }
+
+    /// The source location in the location format of the config, if the record has one.
+    pub fn get_location(&self) -> Option<Location<'_, 'a>> {
+        let has_location = match self.config.location_format {
+            LocationFormat::File => self.get_file().is_some() && self.get_line().is_some(),
+            LocationFormat::ModulePath => self.record.module_path().is_some(),
+        };
+
+        has_location.then_some(Location { writer: self })
+    }
+
+    fn write_location(&self, w: &mut impl Write) -> std::fmt::Result {
+        if self.config.location_format == LocationFormat::ModulePath {
+            return w.write_str(self.record.module_path().unwrap_or_default());
+        }
+
+        let mut file = self.get_file().unwrap_or_default();
+
+        if let Some(prefix) = &self.config.strip_location_prefix {
+            if let Some(stripped) = file.strip_prefix(prefix.as_str()) {
+                file = stripped.trim_start_matches(['/', '\\']);
+            }
+        }
+
+        if self.config.normalize_separators {
+            for (i, part) in file.split('\\').enumerate() {
+                if i > 0 {
+                    w.write_char('/')?;
+                }
+
+                w.write_str(part)?;
+            }
+        } else {
+            w.write_str(file)?;
+        }
+
+        write!(w, ":{}", self.get_line().unwrap_or_default())
+    }
+}
+
+/// Write the datetime in the fixed offset or timezone of the config, or in the local timezone.
+pub(crate) fn write_datetime(
+    datetime: DateTime<Local>,
+    config: &Config,
+    timestamp_format: Option<TimestampFormat>,
+    datetime_format: &str,
+    w: &mut impl Write,
+) -> std::fmt::Result {
+    if let Some(utc_offset) = config.utc_offset {
+        return write_datetime_in(
+            &datetime.with_timezone(&utc_offset),
+            timestamp_format,
+            datetime_format,
+            w,
+        );
+    }
+
+    #[cfg(feature = "timezone")]
+    return write_datetime_in(
+        &datetime.with_timezone(&config.timezone),
+        timestamp_format,
+        datetime_format,
+        w,
+    );
+
+    #[cfg(not(feature = "timezone"))]
+    return write_datetime_in(&datetime, timestamp_format, datetime_format, w);
+}
+
+fn write_datetime_in<Tz: TimeZone>(
+    datetime: &DateTime<Tz>,
+    timestamp_format: Option<TimestampFormat>,
+    datetime_format: &str,
+    w: &mut impl Write,
+) -> std::fmt::Result
+where
+    Tz::Offset: Display,
+{
+    match timestamp_format {
+        Some(timestamp_format) => timestamp_format.write(datetime, w),
+        None => write!(w, "{}", datetime.format(datetime_format)),
+    }
+}
+
+pub(crate) struct Datetime<'w, 'a> {
+    writer: &'w LogWriter<'a>,
+}
+
+impl std::fmt::Display for Datetime<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.writer.write_datetime(f)
+    }
+}
+
+pub(crate) struct Location<'w, 'a> {
+    writer: &'w LogWriter<'a>,
+}
+
+impl std::fmt::Display for Location<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.writer.write_location(f)
+    }
+}
+
+pub(crate) struct Context<'w, 'a> {
+    writer: &'w LogWriter<'a>,
+}
+
+impl std::fmt::Display for Context<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.writer.write_context(f)
+    }
+}
+
+/// Whether the format only changes once per second, i.e. has no fractional seconds (`%f`, or
+/// `%+` which includes them).
+fn is_cacheable_per_second(datetime_format: &str) -> bool {
+    let mut chars = datetime_format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+
+        // skip the padding, width and precision modifiers, e.g. `%.3f` or `%-d`
+        let specifier = chars.find(|c| !matches!(c, '.' | '-' | '_' | '0'..='9' | '#' | ':'));
+
+        if matches!(specifier, Some('f' | '+')) {
+            return false;
+        }
+    }
+
+    true
 }